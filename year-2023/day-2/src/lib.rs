@@ -5,14 +5,60 @@ Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
 Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
 Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green";
 
-struct Game {
-    id: usize,
-    red: usize,
-    blue: usize,
-    green: usize,
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Cubes {
+    pub red: usize,
+    pub green: usize,
+    pub blue: usize,
 }
 
-fn parse_input(input: &str) -> impl Iterator<Item = Game> + '_ {
+impl Cubes {
+    #[must_use]
+    pub fn new(red: usize, green: usize, blue: usize) -> Self {
+        Cubes { red, green, blue }
+    }
+
+    #[must_use]
+    pub fn total(self) -> usize {
+        self.red + self.green + self.blue
+    }
+
+    #[must_use]
+    pub fn contains(self, draw: Cubes) -> bool {
+        draw.red <= self.red && draw.green <= self.green && draw.blue <= self.blue
+    }
+
+    #[must_use]
+    pub fn max(self, other: Cubes) -> Cubes {
+        Cubes {
+            red: self.red.max(other.red),
+            green: self.green.max(other.green),
+            blue: self.blue.max(other.blue),
+        }
+    }
+}
+
+pub struct Game {
+    pub id: usize,
+    pub red: usize,
+    pub blue: usize,
+    pub green: usize,
+    pub draws: Vec<Cubes>,
+}
+
+impl Game {
+    #[must_use]
+    pub fn minimum_bag(&self) -> Cubes {
+        Cubes::new(self.red, self.green, self.blue)
+    }
+}
+
+/// The games recorded in `input`, one per line.
+///
+/// # Panics
+///
+/// Panics when iterating over a line that is not a valid game record.
+pub fn parse_input(input: &str) -> impl Iterator<Item = Game> + '_ {
     input.lines().map(parse_line)
 }
 
@@ -25,20 +71,26 @@ fn parse_line(line: &str) -> Game {
         red: 0,
         blue: 0,
         green: 0,
+        draws: vec![],
     };
-    let subsets = rem.split(";");
+    let subsets = rem.split(';');
     for subset in subsets {
+        let mut draw = Cubes::default();
         let cubes = subset.split(", ");
         for cube in cubes {
-            let (num, color) = cube.trim().split_once(" ").unwrap();
+            let (num, color) = cube.trim().split_once(' ').unwrap();
             let num = num.parse::<usize>().unwrap();
             match color {
-                "red" => game.red = game.red.max(num),
-                "blue" => game.blue = game.blue.max(num),
-                "green" => game.green = game.green.max(num),
+                "red" => draw.red += num,
+                "blue" => draw.blue += num,
+                "green" => draw.green += num,
                 _ => unreachable!(),
             }
         }
+        game.red = game.red.max(draw.red);
+        game.blue = game.blue.max(draw.blue);
+        game.green = game.green.max(draw.green);
+        game.draws.push(draw);
     }
     game
 }
@@ -80,3 +132,100 @@ mod part_2 {
         eprintln!("Solution Day 2 Part 2: [{}]", solution(super::INPUT));
     }
 }
+
+pub mod analysis {
+    use crate::{Cubes, Game};
+
+    #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+    pub struct Violation {
+        pub game_id: usize,
+        pub draw_index: usize,
+        pub draw: Cubes,
+    }
+
+    // The smallest bag that could have produced every game.
+    #[must_use]
+    pub fn minimum_bag(games: &[Game]) -> Cubes {
+        games
+            .iter()
+            .map(Game::minimum_bag)
+            .fold(Cubes::default(), Cubes::max)
+    }
+
+    // Every bag holding exactly `total` cubes that is consistent with all games.
+    #[must_use]
+    pub fn consistent_bags(games: &[Game], total: usize) -> Vec<Cubes> {
+        let min = minimum_bag(games);
+        if min.total() > total {
+            return vec![];
+        }
+        let mut bags = vec![];
+        for red in min.red..=total - min.green - min.blue {
+            for green in min.green..=total - red - min.blue {
+                bags.push(Cubes::new(red, green, total - red - green));
+            }
+        }
+        bags
+    }
+
+    // The games that rule out `bag`, each with the first draw that does not fit in it.
+    #[must_use]
+    pub fn violations(games: &[Game], bag: Cubes) -> Vec<Violation> {
+        games
+            .iter()
+            .filter_map(|game| {
+                let (draw_index, &draw) = game
+                    .draws
+                    .iter()
+                    .enumerate()
+                    .find(|(_, &draw)| !bag.contains(draw))?;
+                Some(Violation {
+                    game_id: game.id,
+                    draw_index,
+                    draw,
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_minimum_bag_example() {
+        let games: Vec<Game> = super::parse_input(super::EXAMPLE_INPUT).collect();
+        assert_eq!(minimum_bag(&games), Cubes::new(20, 13, 15));
+    }
+
+    #[test]
+    fn test_consistent_bags_example() {
+        let games: Vec<Game> = super::parse_input(super::EXAMPLE_INPUT).collect();
+        assert_eq!(consistent_bags(&games, 47), vec![]);
+        assert_eq!(consistent_bags(&games, 48), vec![Cubes::new(20, 13, 15)]);
+        assert_eq!(
+            consistent_bags(&games, 49),
+            vec![
+                Cubes::new(20, 13, 16),
+                Cubes::new(20, 14, 15),
+                Cubes::new(21, 13, 15),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_violations_example() {
+        let games: Vec<Game> = super::parse_input(super::EXAMPLE_INPUT).collect();
+        assert_eq!(
+            violations(&games, Cubes::new(12, 13, 14)),
+            vec![
+                Violation {
+                    game_id: 3,
+                    draw_index: 0,
+                    draw: Cubes::new(20, 8, 6),
+                },
+                Violation {
+                    game_id: 4,
+                    draw_index: 2,
+                    draw: Cubes::new(14, 3, 15),
+                },
+            ]
+        );
+    }
+}
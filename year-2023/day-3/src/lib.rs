@@ -1,5 +1,3 @@
-use std::collections::HashMap;
-
 const INPUT: &str = include_str!("../input_part_1");
 const EXAMPLE_INPUT: &str = r"467..114..
 ...*......
@@ -18,8 +16,8 @@ mod part_1 {
     fn solution(input: &str) -> usize {
         let schematic: Schematic = input.into();
         schematic
-            .map
-            .values()
+            .parts
+            .iter()
             .filter_map(|&part| schematic.get_part_number(part))
             .sum()
     }
@@ -41,8 +39,8 @@ mod part_2 {
     fn solution(input: &str) -> usize {
         let schematic: Schematic = input.into();
         schematic
-            .map
-            .values()
+            .parts
+            .iter()
             .filter_map(|&part| schematic.get_gear_ratio(part))
             .sum()
     }
@@ -59,16 +57,16 @@ mod part_2 {
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
-struct Position(usize, usize);
+pub struct Position(pub usize, pub usize);
 
-#[derive(Debug, Copy, Clone)]
-struct SchematicPart {
-    pos: Position,
-    r#type: PartType,
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SchematicPart {
+    pub pos: Position,
+    pub r#type: PartType,
 }
 
-#[derive(Debug, Copy, Clone)]
-enum PartType {
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PartType {
     Number { number: usize, len: usize },
     Symbol(char),
 }
@@ -82,95 +80,297 @@ impl SchematicPart {
     }
 }
 
-struct Schematic {
-    map: HashMap<Position, SchematicPart>,
-    max_len: usize,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CharClass {
+    // Anything that is not alphanumeric or a `.`
+    Punctuation,
+    Only(Vec<char>),
+}
+
+impl CharClass {
+    fn contains(&self, c: char) -> bool {
+        match self {
+            CharClass::Punctuation => !c.is_alphanumeric() && c != '.',
+            CharClass::Only(chars) => chars.contains(&c),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GearCount {
+    Exactly(usize),
+    AtLeast(usize),
+}
+
+impl GearCount {
+    fn matches(self, count: usize) -> bool {
+        match self {
+            GearCount::Exactly(n) => count == n,
+            GearCount::AtLeast(n) => count >= n,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Combine {
+    Product,
+    Sum,
+}
+
+impl Combine {
+    fn apply(self, numbers: &[usize]) -> usize {
+        match self {
+            Combine::Product => numbers.iter().product(),
+            Combine::Sum => numbers.iter().sum(),
+        }
+    }
+}
+
+// Describes which characters count as symbols and gears, and how a gear's ratio is computed.
+// Gear characters are always treated as symbols.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rules {
+    pub symbols: CharClass,
+    pub gears: CharClass,
+    pub gear_count: GearCount,
+    pub combine: Combine,
+}
+
+impl Rules {
+    fn is_symbol(&self, c: char) -> bool {
+        self.symbols.contains(c) || self.gears.contains(c)
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            symbols: CharClass::Punctuation,
+            gears: CharClass::Only(vec!['*']),
+            gear_count: GearCount::Exactly(2),
+            combine: Combine::Product,
+        }
+    }
+}
+
+// Row-major grid where every cell covered by a part holds the index of that part in `parts`,
+// so each digit of a number points back to the whole number.
+pub struct Schematic {
+    width: usize,
+    height: usize,
+    cells: Vec<Option<usize>>,
+    parts: Vec<SchematicPart>,
+    rules: Rules,
 }
 
 impl Schematic {
-    fn new() -> Self {
+    fn new(width: usize, height: usize, rules: Rules) -> Self {
         Schematic {
-            map: HashMap::new(),
-            max_len: 0,
+            width,
+            height,
+            cells: vec![None; width * height],
+            parts: Vec::new(),
+            rules,
+        }
+    }
+
+    #[must_use]
+    pub fn parse(input: &str, rules: Rules) -> Self {
+        let width = input.lines().map(line_width).max().unwrap_or(0);
+        let height = input.lines().count();
+        let mut parts = Vec::new();
+        for (y, line) in input.lines().enumerate() {
+            parse_line_into(y, line, &rules, |part| parts.push(part));
+        }
+        let mut schematic = Schematic::new(width, height, rules);
+        for part in parts {
+            schematic.put(part);
         }
+        schematic
     }
 
     fn put(&mut self, part: SchematicPart) {
-        self.map.insert(part.pos, part);
-        self.max_len = self.max_len.max(part.len())
+        let index = self.parts.len();
+        let Position(x, y) = part.pos;
+        let start = y * self.width + x;
+        self.cells[start..start + part.len()].fill(Some(index));
+        self.parts.push(part);
+    }
+
+    fn get(&self, x: usize, y: usize) -> Option<usize> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.cells[y * self.width + x]
+    }
+
+    // Indices of all distinct parts in the cells surrounding `part`.
+    fn adjacent(&self, part: SchematicPart) -> impl Iterator<Item = usize> + '_ {
+        let Position(x, y) = part.pos;
+        let x_min = x.saturating_sub(1);
+        let y_min = y.saturating_sub(1);
+        let x_max = x + part.len();
+        let y_max = y + 1;
+        let mut seen = Vec::with_capacity(6);
+        (y_min..=y_max)
+            .flat_map(move |y| (x_min..=x_max).map(move |x| (x, y)))
+            .filter_map(|(x, y)| self.get(x, y))
+            .filter(move |&index| {
+                if self.parts[index].pos == part.pos || seen.contains(&index) {
+                    return false;
+                }
+                seen.push(index);
+                true
+            })
     }
 
     fn get_part_number(&self, part: SchematicPart) -> Option<usize> {
-        let PartType::Number { number, len } = part.r#type else {
+        let PartType::Number { number, .. } = part.r#type else {
             return None;
         };
 
-        // find adjacent parts
-        let x_min = part.pos.0.saturating_sub(1);
-        let y_min = part.pos.1.saturating_sub(1);
-        let x_max = part.pos.0.saturating_add(len);
-        let y_max = part.pos.1.saturating_add(1);
-        for x in x_min..=x_max {
-            for y in y_min..=y_max {
-                if let Some(p) = self.map.get(&Position(x, y)) {
-                    if matches!(p.r#type, PartType::Symbol(_)) {
-                        return Some(number);
-                    }
-                }
-            }
-        }
-        None
+        self.adjacent(part)
+            .any(|index| matches!(self.parts[index].r#type, PartType::Symbol(_)))
+            .then_some(number)
     }
 
     fn get_gear_ratio(&self, part: SchematicPart) -> Option<usize> {
-        if !matches!(part.r#type, PartType::Symbol('*')) {
+        let PartType::Symbol(symbol) = part.r#type else {
+            return None;
+        };
+        if !self.rules.gears.contains(symbol) {
             return None;
         }
 
-        let mut product: Option<usize> = None;
+        let numbers: Vec<usize> = self
+            .adjacent(part)
+            .filter_map(|index| match self.parts[index].r#type {
+                PartType::Number { number, .. } => Some(number),
+                PartType::Symbol(_) => None,
+            })
+            .collect();
+        self.rules
+            .gear_count
+            .matches(numbers.len())
+            .then(|| self.rules.combine.apply(&numbers))
+    }
+}
 
-        // find adjacent parts
-        let x_min = part.pos.0.saturating_sub(self.max_len + 1);
-        let y_min = part.pos.1.saturating_sub(1);
-        let x_max = part.pos.0.saturating_add(1);
-        let y_max = part.pos.1.saturating_add(1);
-        for x in (x_min..=x_max).rev() {
-            for y in y_min..=y_max {
-                if let Some(p) = self.map.get(&Position(x, y)) {
-                    if let PartType::Number { number, len } = p.r#type {
-                        if x + len < part.pos.0 {
-                            continue;
-                        }
-                        match product {
-                            None => product = Some(number),
-                            Some(product) => return Some(product * number),
-                        }
+// Ad-hoc queries about how the parts of a schematic relate to each other.
+impl Schematic {
+    #[must_use]
+    pub fn parts(&self) -> &[SchematicPart] {
+        &self.parts
+    }
+
+    // The part covering a cell, a number covers every cell of its digits.
+    #[must_use]
+    pub fn part_at(&self, pos: Position) -> Option<SchematicPart> {
+        self.get(pos.0, pos.1).map(|index| self.parts[index])
+    }
+
+    #[must_use]
+    pub fn number_at(&self, pos: Position) -> Option<SchematicPart> {
+        self.part_at(pos)
+            .filter(|part| matches!(part.r#type, PartType::Number { .. }))
+    }
+
+    #[must_use]
+    pub fn numbers_adjacent_to(&self, symbol: SchematicPart) -> Vec<SchematicPart> {
+        if !matches!(symbol.r#type, PartType::Symbol(_)) {
+            return vec![];
+        }
+        self.adjacent_parts(symbol)
+            .filter(|part| matches!(part.r#type, PartType::Number { .. }))
+            .collect()
+    }
+
+    #[must_use]
+    pub fn symbols_adjacent_to(&self, number: SchematicPart) -> Vec<SchematicPart> {
+        if !matches!(number.r#type, PartType::Number { .. }) {
+            return vec![];
+        }
+        self.adjacent_parts(number)
+            .filter(|part| matches!(part.r#type, PartType::Symbol(_)))
+            .collect()
+    }
+
+    // Groups of parts linked by numbers touching symbols, in the order their first part appears.
+    // Numbers without adjacent symbols and symbols without adjacent numbers form their own group.
+    #[must_use]
+    pub fn components(&self) -> Vec<Vec<SchematicPart>> {
+        let mut component_of: Vec<Option<usize>> = vec![None; self.parts.len()];
+        let mut components = Vec::new();
+        for start in 0..self.parts.len() {
+            if component_of[start].is_some() {
+                continue;
+            }
+            let id = components.len();
+            component_of[start] = Some(id);
+            let mut members = vec![start];
+            let mut queue = vec![start];
+            while let Some(index) = queue.pop() {
+                let part = self.parts[index];
+                for neighbour in self.adjacent(part) {
+                    let linked = matches!(
+                        (part.r#type, self.parts[neighbour].r#type),
+                        (PartType::Number { .. }, PartType::Symbol(_))
+                            | (PartType::Symbol(_), PartType::Number { .. })
+                    );
+                    if linked && component_of[neighbour].is_none() {
+                        component_of[neighbour] = Some(id);
+                        members.push(neighbour);
+                        queue.push(neighbour);
                     }
                 }
             }
+            members.sort_unstable();
+            components.push(members.into_iter().map(|i| self.parts[i]).collect());
         }
-        None
+        components
+    }
+
+    fn adjacent_parts(&self, part: SchematicPart) -> impl Iterator<Item = SchematicPart> + '_ {
+        self.adjacent(part).map(|index| self.parts[index])
     }
 }
 
 impl From<&str> for Schematic {
     fn from(value: &str) -> Self {
-        let mut schematic = Schematic::new();
-        value
-            .lines()
-            .enumerate()
-            .for_each(|(y, line)| crate::parse_line_into(y, line, &mut schematic));
-        schematic
+        Schematic::parse(value, Rules::default())
+    }
+}
+
+// Width of a line in characters, columns are counted in chars rather than bytes.
+fn line_width(line: &str) -> usize {
+    if line.is_ascii() {
+        line.len()
+    } else {
+        line.chars().count()
+    }
+}
+
+fn parse_line_into(y: usize, line: &str, rules: &Rules, put: impl FnMut(SchematicPart)) {
+    // ascii lines skip utf-8 decoding
+    if line.is_ascii() {
+        parse_chars_into(y, line.bytes().map(char::from), rules, put);
+    } else {
+        parse_chars_into(y, line.chars(), rules, put);
     }
 }
 
-fn parse_line_into(y: usize, line: &str, schematic: &mut Schematic) {
+fn parse_chars_into(
+    y: usize,
+    chars: impl Iterator<Item = char>,
+    rules: &Rules,
+    mut put: impl FnMut(SchematicPart),
+) {
     let mut num: Option<usize> = None;
     let mut start: usize = 0;
     let mut len: usize = 0;
-    for (index, byte) in line.as_bytes().iter().enumerate() {
-        if byte.is_ascii_digit() {
-            let digit = (byte - b'0') as usize;
+    for (index, c) in chars.enumerate() {
+        if c.is_ascii_digit() {
+            let digit = (c as u8 - b'0') as usize;
             match num {
                 None => {
                     num = {
@@ -183,7 +383,7 @@ fn parse_line_into(y: usize, line: &str, schematic: &mut Schematic) {
             len += 1;
         } else {
             if let Some(n) = num {
-                schematic.put(SchematicPart {
+                put(SchematicPart {
                     pos: Position(start, y),
                     r#type: PartType::Number { number: n, len },
                 });
@@ -191,18 +391,488 @@ fn parse_line_into(y: usize, line: &str, schematic: &mut Schematic) {
                 len = 0;
             }
 
-            if !byte.is_ascii_alphanumeric() && *byte != b'.' {
-                schematic.put(SchematicPart {
+            if rules.is_symbol(c) {
+                put(SchematicPart {
                     pos: Position(index, y),
-                    r#type: PartType::Symbol(*byte as char),
+                    r#type: PartType::Symbol(c),
                 });
             }
         }
     }
     if let Some(n) = num {
-        schematic.put(SchematicPart {
+        put(SchematicPart {
             pos: Position(start, y),
             r#type: PartType::Number { number: n, len },
         });
     }
 }
+
+mod variants {
+    use crate::{Rules, Schematic};
+
+    fn part_numbers(input: &str, rules: Rules) -> usize {
+        let schematic = Schematic::parse(input, rules);
+        schematic
+            .parts
+            .iter()
+            .filter_map(|&part| schematic.get_part_number(part))
+            .sum()
+    }
+
+    fn gear_ratios(input: &str, rules: Rules) -> usize {
+        let schematic = Schematic::parse(input, rules);
+        schematic
+            .parts
+            .iter()
+            .filter_map(|&part| schematic.get_gear_ratio(part))
+            .sum()
+    }
+
+    #[test]
+    fn test_default_rules_example() {
+        assert_eq!(part_numbers(super::EXAMPLE_INPUT, Rules::default()), 4361);
+        assert_eq!(gear_ratios(super::EXAMPLE_INPUT, Rules::default()), 467_835);
+    }
+
+    #[test]
+    fn test_restricted_symbols_example() {
+        use crate::CharClass;
+
+        let rules = Rules {
+            symbols: CharClass::Only(vec!['#', '$']),
+            gears: CharClass::Only(vec![]),
+            ..Rules::default()
+        };
+        assert_eq!(part_numbers(super::EXAMPLE_INPUT, rules), 633 + 664);
+    }
+
+    #[test]
+    fn test_unicode_symbols() {
+        let input = "467..114..\n...§......\n..35..633.\n€€€€€€#...";
+        let schematic = Schematic::parse(input, Rules::default());
+        assert_eq!(schematic.width, 10);
+        assert_eq!(
+            schematic
+                .get(3, 1)
+                .map(|index| schematic.parts[index].r#type),
+            Some(crate::PartType::Symbol('§'))
+        );
+        assert_eq!(part_numbers(input, Rules::default()), 467 + 35 + 633);
+    }
+
+    #[test]
+    fn test_unicode_gears() {
+        use crate::CharClass;
+
+        let input = "..12\n.→..\n3...";
+        let rules = Rules {
+            gears: CharClass::Only(vec!['→']),
+            ..Rules::default()
+        };
+        assert_eq!(gear_ratios(input, rules), 36);
+    }
+
+    #[test]
+    fn test_summed_gears_example() {
+        use crate::{CharClass, Combine, GearCount};
+
+        let rules = Rules {
+            gears: CharClass::Only(vec!['*', '+']),
+            gear_count: GearCount::AtLeast(1),
+            combine: Combine::Sum,
+            ..Rules::default()
+        };
+        let expected = (467 + 35) + 617 + 592 + (755 + 598);
+        assert_eq!(gear_ratios(super::EXAMPLE_INPUT, rules), expected);
+    }
+}
+
+pub mod render {
+    use std::fmt::Write;
+
+    use crate::{PartType, Rules, Schematic};
+
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    enum Highlight {
+        PartNumber,
+        IsolatedNumber,
+        Gear,
+        Symbol,
+        Empty,
+    }
+
+    impl Highlight {
+        fn ansi(self) -> &'static str {
+            match self {
+                Highlight::PartNumber => "\x1b[32m",
+                Highlight::IsolatedNumber => "\x1b[31m",
+                Highlight::Gear => "\x1b[1;33m",
+                Highlight::Symbol => "\x1b[1m",
+                Highlight::Empty => "\x1b[2m",
+            }
+        }
+
+        fn class(self) -> &'static str {
+            match self {
+                Highlight::PartNumber => "part",
+                Highlight::IsolatedNumber => "isolated",
+                Highlight::Gear => "gear",
+                Highlight::Symbol => "symbol",
+                Highlight::Empty => "empty",
+            }
+        }
+    }
+
+    // Rows of the schematic split into runs of characters sharing the same highlight, characters
+    // that are not part of a part are shown as they are in the input.
+    fn runs(input: &str, rules: Rules) -> Vec<Vec<(Highlight, String)>> {
+        let schematic = Schematic::parse(input, rules);
+        let highlights: Vec<Highlight> = schematic
+            .parts
+            .iter()
+            .map(|&part| match part.r#type {
+                PartType::Number { .. } if schematic.get_part_number(part).is_some() => {
+                    Highlight::PartNumber
+                }
+                PartType::Number { .. } => Highlight::IsolatedNumber,
+                PartType::Symbol(_) if schematic.get_gear_ratio(part).is_some() => Highlight::Gear,
+                PartType::Symbol(_) => Highlight::Symbol,
+            })
+            .collect();
+
+        input
+            .lines()
+            .enumerate()
+            .map(|(y, line)| {
+                let mut row: Vec<(Highlight, String)> = Vec::new();
+                // short lines are padded to the width of the schematic
+                let mut chars = line.chars().chain(std::iter::repeat('.'));
+                for x in 0..schematic.width {
+                    let c = chars.next().unwrap();
+                    let highlight = schematic
+                        .get(x, y)
+                        .map_or(Highlight::Empty, |index| highlights[index]);
+                    match row.last_mut() {
+                        Some((last, text)) if *last == highlight => text.push(c),
+                        _ => row.push((highlight, c.to_string())),
+                    }
+                }
+                row
+            })
+            .collect()
+    }
+
+    // The schematic with ANSI colours for a terminal.
+    #[must_use]
+    pub fn to_ansi(input: &str, rules: Rules) -> String {
+        let mut output = String::new();
+        for row in runs(input, rules) {
+            for (highlight, text) in row {
+                let _ = write!(output, "{}{text}\x1b[0m", highlight.ansi());
+            }
+            output.push('\n');
+        }
+        output
+    }
+
+    // The schematic as a standalone HTML page.
+    #[must_use]
+    pub fn to_html(input: &str, rules: Rules) -> String {
+        let mut output = String::from(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Schematic</title>
+<style>
+body { background: #0f0f23; color: #cccccc; }
+.part { color: #00cc00; }
+.isolated { color: #ff4040; }
+.gear { color: #ffff66; font-weight: bold; }
+.symbol { font-weight: bold; }
+.empty { color: #555555; }
+</style>
+</head>
+<body>
+<pre>
+"#,
+        );
+        for row in runs(input, rules) {
+            for (highlight, text) in row {
+                let text = text
+                    .replace('&', "&amp;")
+                    .replace('<', "&lt;")
+                    .replace('>', "&gt;");
+                let _ = write!(
+                    output,
+                    r#"<span class="{}">{text}</span>"#,
+                    highlight.class()
+                );
+            }
+            output.push('\n');
+        }
+        output.push_str("</pre>\n</body>\n</html>\n");
+        output
+    }
+
+    #[test]
+    fn test_ansi_example() {
+        let input = "467..114..\n...*......\n..35..633.";
+        let expected = "\x1b[32m467\x1b[0m\x1b[2m..\x1b[0m\x1b[31m114\x1b[0m\x1b[2m..\x1b[0m\n\
+                        \x1b[2m...\x1b[0m\x1b[1;33m*\x1b[0m\x1b[2m......\x1b[0m\n\
+                        \x1b[2m..\x1b[0m\x1b[32m35\x1b[0m\x1b[2m..\x1b[0m\x1b[31m633\x1b[0m\x1b[2m.\x1b[0m\n";
+        assert_eq!(to_ansi(input, Rules::default()), expected);
+    }
+
+    #[test]
+    fn test_ansi_keeps_other_characters() {
+        use crate::CharClass;
+
+        let rules = Rules {
+            symbols: CharClass::Only(vec!['#']),
+            gears: CharClass::Only(vec![]),
+            ..Rules::default()
+        };
+        let expected = "\x1b[2mab\x1b[0m\x1b[32m12\x1b[0m\x1b[1m#\x1b[0m\n\
+                        \x1b[2m+.\x1b[0m\x1b[31m3\x1b[0m\x1b[2m..\x1b[0m\n";
+        assert_eq!(to_ansi("ab12#\n+.3", rules), expected);
+    }
+
+    #[test]
+    fn test_html_example() {
+        let html = to_html("..617*\n....<.", Rules::default());
+        assert!(html.contains(
+            r#"<span class="empty">..</span><span class="part">617</span><span class="symbol">*</span>"#
+        ));
+        assert!(html.contains(
+            r#"<span class="empty">....</span><span class="symbol">&lt;</span><span class="empty">.</span>"#
+        ));
+    }
+}
+
+mod queries {
+    use crate::{PartType, Position, SchematicPart};
+
+    fn number(x: usize, y: usize, number: usize, len: usize) -> SchematicPart {
+        SchematicPart {
+            pos: Position(x, y),
+            r#type: PartType::Number { number, len },
+        }
+    }
+
+    fn symbol(x: usize, y: usize, symbol: char) -> SchematicPart {
+        SchematicPart {
+            pos: Position(x, y),
+            r#type: PartType::Symbol(symbol),
+        }
+    }
+
+    #[test]
+    fn test_number_at_example() {
+        let schematic: crate::Schematic = super::EXAMPLE_INPUT.into();
+        for x in 0..3 {
+            assert_eq!(
+                schematic.number_at(Position(x, 0)),
+                Some(number(0, 0, 467, 3))
+            );
+        }
+        assert_eq!(schematic.number_at(Position(3, 0)), None);
+        assert_eq!(schematic.number_at(Position(3, 1)), None);
+        assert_eq!(schematic.number_at(Position(100, 100)), None);
+    }
+
+    #[test]
+    fn test_adjacency_example() {
+        let schematic: crate::Schematic = super::EXAMPLE_INPUT.into();
+        assert_eq!(
+            schematic.numbers_adjacent_to(symbol(3, 1, '*')),
+            vec![number(0, 0, 467, 3), number(2, 2, 35, 2)]
+        );
+        assert_eq!(
+            schematic.symbols_adjacent_to(number(0, 0, 467, 3)),
+            vec![symbol(3, 1, '*')]
+        );
+        assert_eq!(schematic.symbols_adjacent_to(number(5, 0, 114, 3)), vec![]);
+    }
+
+    #[test]
+    fn test_components_example() {
+        let schematic: crate::Schematic = super::EXAMPLE_INPUT.into();
+        let components = schematic.components();
+        assert_eq!(components.len(), 8);
+        assert_eq!(
+            components[0],
+            vec![number(0, 0, 467, 3), symbol(3, 1, '*'), number(2, 2, 35, 2)]
+        );
+        assert_eq!(components[1], vec![number(5, 0, 114, 3)]);
+        assert_eq!(
+            components.iter().map(Vec::len).sum::<usize>(),
+            schematic.parts().len()
+        );
+    }
+
+    #[test]
+    fn test_queries_with_rules() {
+        use crate::{CharClass, Rules, Schematic};
+
+        let rules = Rules {
+            symbols: CharClass::Only(vec!['#']),
+            gears: CharClass::Only(vec![]),
+            ..Rules::default()
+        };
+        let schematic = Schematic::parse(super::EXAMPLE_INPUT, rules);
+        assert_eq!(schematic.part_at(Position(3, 1)), None);
+        assert_eq!(
+            schematic.numbers_adjacent_to(symbol(6, 3, '#')),
+            vec![number(6, 2, 633, 3)]
+        );
+        assert_eq!(schematic.components().len(), 10);
+    }
+}
+
+// Parts keyed by their start in a `HashMap`, so a gear probes every cell an adjacent number
+// could start in. The grid has to agree with it on the part numbers and gear ratios.
+mod sparse {
+    use std::collections::HashMap;
+
+    use crate::{PartType, Position, Rules, SchematicPart};
+
+    struct Schematic {
+        map: HashMap<Position, SchematicPart>,
+        max_len: usize,
+    }
+
+    impl Schematic {
+        fn new() -> Self {
+            Schematic {
+                map: HashMap::new(),
+                max_len: 0,
+            }
+        }
+
+        fn put(&mut self, part: SchematicPart) {
+            self.map.insert(part.pos, part);
+            self.max_len = self.max_len.max(part.len());
+        }
+
+        fn get_part_number(&self, part: SchematicPart) -> Option<usize> {
+            let PartType::Number { number, len } = part.r#type else {
+                return None;
+            };
+
+            // find adjacent parts
+            let x_min = part.pos.0.saturating_sub(1);
+            let y_min = part.pos.1.saturating_sub(1);
+            let x_max = part.pos.0.saturating_add(len);
+            let y_max = part.pos.1.saturating_add(1);
+            for x in x_min..=x_max {
+                for y in y_min..=y_max {
+                    if let Some(p) = self.map.get(&Position(x, y)) {
+                        if matches!(p.r#type, PartType::Symbol(_)) {
+                            return Some(number);
+                        }
+                    }
+                }
+            }
+            None
+        }
+
+        fn get_gear_ratio(&self, part: SchematicPart) -> Option<usize> {
+            if !matches!(part.r#type, PartType::Symbol('*')) {
+                return None;
+            }
+
+            let mut product: Option<usize> = None;
+
+            // find adjacent parts
+            let x_min = part.pos.0.saturating_sub(self.max_len + 1);
+            let y_min = part.pos.1.saturating_sub(1);
+            let x_max = part.pos.0.saturating_add(1);
+            let y_max = part.pos.1.saturating_add(1);
+            for x in (x_min..=x_max).rev() {
+                for y in y_min..=y_max {
+                    if let Some(p) = self.map.get(&Position(x, y)) {
+                        if let PartType::Number { number, len } = p.r#type {
+                            if x + len < part.pos.0 {
+                                continue;
+                            }
+                            match product {
+                                None => product = Some(number),
+                                Some(product) => return Some(product * number),
+                            }
+                        }
+                    }
+                }
+            }
+            None
+        }
+    }
+
+    impl From<&str> for Schematic {
+        fn from(value: &str) -> Self {
+            let mut schematic = Schematic::new();
+            let rules = Rules::default();
+            value.lines().enumerate().for_each(|(y, line)| {
+                crate::parse_line_into(y, line, &rules, |part| schematic.put(part));
+            });
+            schematic
+        }
+    }
+
+    fn solve_sparse(input: &str) -> (usize, usize) {
+        let schematic: Schematic = input.into();
+        let part_numbers = schematic
+            .map
+            .values()
+            .filter_map(|&part| schematic.get_part_number(part))
+            .sum();
+        let gear_ratios = schematic
+            .map
+            .values()
+            .filter_map(|&part| schematic.get_gear_ratio(part))
+            .sum();
+        (part_numbers, gear_ratios)
+    }
+
+    fn solve_dense(input: &str) -> (usize, usize) {
+        let schematic: crate::Schematic = input.into();
+        let part_numbers = schematic
+            .parts
+            .iter()
+            .filter_map(|&part| schematic.get_part_number(part))
+            .sum();
+        let gear_ratios = schematic
+            .parts
+            .iter()
+            .filter_map(|&part| schematic.get_gear_ratio(part))
+            .sum();
+        (part_numbers, gear_ratios)
+    }
+
+    #[test]
+    fn test_dense_matches_sparse() {
+        assert_eq!(
+            solve_dense(super::EXAMPLE_INPUT),
+            solve_sparse(super::EXAMPLE_INPUT)
+        );
+        assert_eq!(solve_dense(super::INPUT), solve_sparse(super::INPUT));
+    }
+
+    // Parsing and solving both parts on the puzzle input, averaged over 20 runs per schematic.
+    #[test]
+    #[ignore = "timing, run with --release --ignored --nocapture"]
+    fn bench_dense_against_sparse() {
+        const ITERATIONS: u32 = 20;
+        let start = std::time::Instant::now();
+        for _ in 0..ITERATIONS {
+            std::hint::black_box(solve_sparse(std::hint::black_box(super::INPUT)));
+        }
+        let sparse = start.elapsed() / ITERATIONS;
+        let start = std::time::Instant::now();
+        for _ in 0..ITERATIONS {
+            std::hint::black_box(solve_dense(std::hint::black_box(super::INPUT)));
+        }
+        let dense = start.elapsed() / ITERATIONS;
+        eprintln!("Day 3 sparse schematic: [{sparse:?}], dense schematic: [{dense:?}]");
+    }
+}
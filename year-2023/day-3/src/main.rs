@@ -0,0 +1,33 @@
+use std::process::exit;
+use std::{env, fs};
+
+use day_3::render::{to_ansi, to_html};
+use day_3::Rules;
+
+// Shows a schematic with its part numbers, isolated numbers and gears highlighted:
+//   day-3 <schematic> [--html <out.html>]
+// Without `--html` the schematic is printed with ANSI colours.
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (path, html) = match &args[..] {
+        [path] => (path, None),
+        [path, flag, out] if flag == "--html" => (path, Some(out)),
+        _ => {
+            eprintln!("usage: day-3 <schematic> [--html <out.html>]");
+            exit(2);
+        }
+    };
+    let input = fs::read_to_string(path).unwrap_or_else(|e| {
+        eprintln!("could not read {path}: {e}");
+        exit(1);
+    });
+    let input = input.replace("\r\n", "\n");
+
+    match html {
+        Some(out) => fs::write(out, to_html(&input, Rules::default())).unwrap_or_else(|e| {
+            eprintln!("could not write {out}: {e}");
+            exit(1);
+        }),
+        None => print!("{}", to_ansi(&input, Rules::default())),
+    }
+}
@@ -1,4 +1,5 @@
-use std::collections::HashSet;
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
 
 const INPUT: &str = include_str!("../puzzle_input");
 const EXAMPLE_INPUT: &str = "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
@@ -8,57 +9,375 @@ Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
 Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
 Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
 
+// Set of numbers in 0..=255 stored as a fixed width bitset.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+struct NumberSet([u64; 4]);
+
+impl NumberSet {
+    fn insert(&mut self, n: u8) {
+        self.0[usize::from(n >> 6)] |= 1 << (n & 63);
+    }
+
+    fn contains(&self, n: u8) -> bool {
+        self.0[usize::from(n >> 6)] & (1 << (n & 63)) != 0
+    }
+
+    fn intersection_count(&self, other: &NumberSet) -> usize {
+        self.0
+            .iter()
+            .zip(other.0)
+            .map(|(a, b)| (a & b).count_ones() as usize)
+            .sum()
+    }
+}
+
 #[derive(Debug, Clone)]
-struct Card {
-    id: usize,
-    numbers: Vec<u8>,
-    winning_numbers: HashSet<u8>,
+pub struct Card {
+    pub id: usize,
+    numbers: NumberSet,
+    winning_numbers: NumberSet,
+    // how many numbers were listed on either side, including duplicates
+    lengths: (usize, usize),
+    // numbers listed more than once on the same side
+    duplicates: NumberSet,
+    // every repeat of a number on the left side, which matches again for every repeat
+    repeated_numbers: Vec<u8>,
 }
 
 impl Card {
-    fn winning_number_count(&self) -> usize {
-        self.numbers
+    #[must_use]
+    pub fn winning_number_count(&self) -> usize {
+        let repeats = self
+            .repeated_numbers
             .iter()
-            .filter(|&n| self.winning_numbers.contains(n))
-            .count()
+            .filter(|&&n| self.winning_numbers.contains(n))
+            .count();
+        self.numbers.intersection_count(&self.winning_numbers) + repeats
+    }
+
+    #[must_use]
+    pub fn winnings(&self) -> Option<usize> {
+        self.score(&Scoring::Doubling)
     }
 
-    fn winnings(&self) -> usize {
-        let count = self.winning_number_count();
-        if count == 0 {
-            return 0;
+    #[must_use]
+    pub fn score(&self, scoring: &Scoring) -> Option<usize> {
+        scoring.score(self.winning_number_count())
+    }
+}
+
+// How many points a card is worth for its number of matches, `None` if that does not fit in a
+// `usize`, which takes more than 64 matches when doubling and more than 93 for fibonacci.
+pub enum Scoring {
+    // 1 point for the first match, doubled for every match after that
+    Doubling,
+    // 1 point per match
+    Linear,
+    // the nth fibonacci number for n matches
+    Fibonacci,
+    Custom(Box<dyn Fn(usize) -> usize>),
+}
+
+impl Scoring {
+    #[must_use]
+    pub fn score(&self, matches: usize) -> Option<usize> {
+        match self {
+            Scoring::Doubling if matches == 0 => Some(0),
+            Scoring::Doubling => 2usize.checked_pow(u32::try_from(matches - 1).ok()?),
+            Scoring::Linear => Some(matches),
+            Scoring::Fibonacci => {
+                // the next number is only needed while there are matches left
+                let (mut a, mut b) = (0usize, Some(1usize));
+                for _ in 0..matches {
+                    (a, b) = (b?, b.and_then(|b| b.checked_add(a)));
+                }
+                Some(a)
+            }
+            Scoring::Custom(f) => Some(f(matches)),
         }
-        2usize.pow(u32::try_from(count - 1).unwrap())
     }
 }
 
-fn parse_cards(input: &str) -> impl Iterator<Item = Card> + '_ {
+// What happens to copies won past the last card of the table.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TableEnd {
+    // copies past the end are lost
+    Truncate,
+    // copies past the end all go to the last card
+    Clamp,
+    // copies past the end continue from the first card, but cards that were already played
+    // don't win anything with them
+    Wrap,
+}
+
+// Which cards a card with n matches wins copies of: n cards starting `offset` cards after it.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct CopyWindow {
+    pub offset: usize,
+    pub table_end: TableEnd,
+}
+
+impl Default for CopyWindow {
+    fn default() -> Self {
+        CopyWindow {
+            offset: 1,
+            table_end: TableEnd::Truncate,
+        }
+    }
+}
+
+impl CopyWindow {
+    // Ids of the cards won by card `id` in a table spanning `ids`.
+    fn targets(
+        self,
+        id: usize,
+        matches: usize,
+        ids: RangeInclusive<usize>,
+    ) -> impl Iterator<Item = usize> {
+        let start = id + self.offset;
+        let (first, last) = ids.into_inner();
+        (start..start + matches).filter_map(move |target| match self.table_end {
+            _ if target <= last => Some(target),
+            TableEnd::Truncate => None,
+            TableEnd::Clamp => Some(last),
+            TableEnd::Wrap => Some(first + (target - first) % (last - first + 1)),
+        })
+    }
+
+    // Total instances of every card, the original plus every copy won, in the order of `cards`.
+    // Cards are played in id order and copies of ids missing from the table are lost.
+    #[must_use]
+    pub fn copies(self, cards: &[Card]) -> Vec<usize> {
+        self.play(cards, |_, _, _| {})
+    }
+
+    // Like `copies`, calling `won(i, t, copies)` every time the instances of `cards[i]` win
+    // `copies` copies of `cards[t]`.
+    fn play(self, cards: &[Card], mut won: impl FnMut(usize, usize, usize)) -> Vec<usize> {
+        let index_of = index_by_id(cards);
+        let mut card_copies = vec![1; cards.len()];
+        let Some(ids) = id_range(&index_of) else {
+            return card_copies;
+        };
+        for (&id, &i) in &index_of {
+            let copies = card_copies[i];
+            for target in self.targets(id, cards[i].winning_number_count(), ids.clone()) {
+                if let Some(&t) = index_of.get(&target) {
+                    card_copies[t] += copies;
+                    won(i, t, copies);
+                }
+            }
+        }
+        card_copies
+    }
+}
+
+fn index_by_id(cards: &[Card]) -> BTreeMap<usize, usize> {
+    cards
+        .iter()
+        .enumerate()
+        .map(|(index, card)| (card.id, index))
+        .collect()
+}
+
+fn id_range(index_of: &BTreeMap<usize, usize>) -> Option<RangeInclusive<usize>> {
+    let (&first, _) = index_of.first_key_value()?;
+    let (&last, _) = index_of.last_key_value()?;
+    Some(first..=last)
+}
+
+/// The cards listed in `input`, one per line.
+///
+/// # Panics
+///
+/// Panics when iterating over a line that is not a valid card.
+pub fn parse_cards(input: &str) -> impl Iterator<Item = Card> + '_ {
     input.lines().map(parse_line)
 }
 
 fn parse_line(line: &str) -> Card {
-    let line = line.strip_prefix("Card").unwrap();
-    let (id, line) = line.split_once(':').unwrap();
-    let (numbers, winners) = line.split_once('|').unwrap();
+    let line = line.strip_prefix("Card").unwrap().as_bytes();
+    let Some(colon) = line.iter().position(|&b| b == b':') else {
+        invalid_card(line);
+    };
+    let id = line[..colon].trim_ascii();
+    if id.is_empty() || !id.iter().all(u8::is_ascii_digit) {
+        invalid_card(line);
+    }
+    let id = id
+        .iter()
+        .try_fold(0usize, |id, &b| {
+            id.checked_mul(10)?.checked_add(usize::from(b - b'0'))
+        })
+        .unwrap_or_else(|| invalid_card(line));
+    let bytes = line[colon + 1..].iter().copied();
+
+    let mut numbers = NumberSet::default();
+    let mut winning_numbers = NumberSet::default();
+    let mut duplicates = NumberSet::default();
+    let mut repeated_numbers = vec![];
+    let mut lengths = (0, 0);
+    let (mut set, mut len, mut repeats) =
+        (&mut numbers, &mut lengths.0, Some(&mut repeated_numbers));
+    let mut current: Option<u8> = None;
+    for b in bytes.chain([b' ']) {
+        match b {
+            b'0'..=b'9' => {
+                let n = current.unwrap_or(0);
+                current = Some(n.checked_mul(10).unwrap().checked_add(b - b'0').unwrap());
+            }
+            b if b == b'|' || b.is_ascii_whitespace() => {
+                if let Some(n) = current.take() {
+                    if set.contains(n) {
+                        duplicates.insert(n);
+                        if let Some(repeats) = repeats.as_mut() {
+                            repeats.push(n);
+                        }
+                    }
+                    set.insert(n);
+                    *len += 1;
+                }
+                if b == b'|' {
+                    (set, len, repeats) = (&mut winning_numbers, &mut lengths.1, None);
+                }
+            }
+            _ => invalid_card(line),
+        }
+    }
     Card {
-        id: id.trim().parse().unwrap(),
-        numbers: numbers
-            .trim()
-            .split_ascii_whitespace()
-            .map(|s| s.parse().unwrap())
-            .collect(),
-        winning_numbers: winners
-            .trim()
-            .split_ascii_whitespace()
-            .map(|s| s.parse().unwrap())
-            .collect(),
+        id,
+        numbers,
+        winning_numbers,
+        lengths,
+        duplicates,
+        repeated_numbers,
+    }
+}
+
+fn invalid_card(line: &[u8]) -> ! {
+    panic!("invalid card: {}", String::from_utf8_lossy(line))
+}
+
+// Cards parsed with `str::split` into a `Vec` and a `HashSet`, the simplest reading of the
+// puzzle. The bitset parser has to count the same matches, repeated numbers included.
+mod hash_set {
+    use std::collections::HashSet;
+
+    struct Card {
+        numbers: Vec<u8>,
+        winning_numbers: HashSet<u8>,
+    }
+
+    impl Card {
+        fn winning_number_count(&self) -> usize {
+            self.numbers
+                .iter()
+                .filter(|&n| self.winning_numbers.contains(n))
+                .count()
+        }
+    }
+
+    fn parse_line(line: &str) -> Card {
+        let line = line.strip_prefix("Card").unwrap();
+        let (_, line) = line.split_once(':').unwrap();
+        let (numbers, winners) = line.split_once('|').unwrap();
+        Card {
+            numbers: numbers
+                .trim()
+                .split_ascii_whitespace()
+                .map(|s| s.parse().unwrap())
+                .collect(),
+            winning_numbers: winners
+                .trim()
+                .split_ascii_whitespace()
+                .map(|s| s.parse().unwrap())
+                .collect(),
+        }
+    }
+
+    fn count_hash_set(input: &str) -> usize {
+        input
+            .lines()
+            .map(|line| parse_line(line).winning_number_count())
+            .sum()
+    }
+
+    fn count_bitset(input: &str) -> usize {
+        super::parse_cards(input)
+            .map(|card| card.winning_number_count())
+            .sum()
+    }
+
+    #[test]
+    fn test_bitset_matches_hash_set() {
+        assert_eq!(
+            count_bitset(super::EXAMPLE_INPUT),
+            count_hash_set(super::EXAMPLE_INPUT)
+        );
+        assert_eq!(count_bitset(super::INPUT), count_hash_set(super::INPUT));
+        // repeats on the left side match once per repeat, repeats on the right side only once
+        let duplicates = "Card 1: 5 5 | 5 7
+Card 2: 3 3 3 9 | 3 3 9 9
+Card 3: 4 4 | 1 2";
+        assert_eq!(count_hash_set(duplicates), 2 + 4);
+        assert_eq!(count_bitset(duplicates), count_hash_set(duplicates));
+    }
+
+    // Parsing the puzzle input and counting its matches, averaged over 100 runs per card type.
+    #[test]
+    #[ignore = "timing, run with --release --ignored --nocapture"]
+    fn bench_bitset_against_hash_set() {
+        const ITERATIONS: u32 = 100;
+        let start = std::time::Instant::now();
+        for _ in 0..ITERATIONS {
+            std::hint::black_box(count_hash_set(std::hint::black_box(super::INPUT)));
+        }
+        let hash_set = start.elapsed() / ITERATIONS;
+        let start = std::time::Instant::now();
+        for _ in 0..ITERATIONS {
+            std::hint::black_box(count_bitset(std::hint::black_box(super::INPUT)));
+        }
+        let bitset = start.elapsed() / ITERATIONS;
+        eprintln!("Day 4 hash set cards: [{hash_set:?}], bitset cards: [{bitset:?}]");
+    }
+}
+
+mod parsing {
+    #[test]
+    fn test_parse_line_large_numbers() {
+        let card = super::parse_line("Card 300: 0 64 128 255 | 255  1 64 200");
+        assert_eq!(card.id, 300);
+        assert!(card.numbers.contains(0) && card.numbers.contains(255));
+        assert!(!card.numbers.contains(1));
+        assert_eq!(card.winning_number_count(), 2);
+    }
+
+    #[test]
+    fn test_parse_line_whitespace() {
+        let card = super::parse_line("Card\t7 :\t41 48\t| 48  41 9\r");
+        assert_eq!(card.id, 7);
+        assert_eq!(card.lengths, (2, 3));
+        assert_eq!(card.winning_number_count(), 2);
+        let crlf: Vec<crate::Card> =
+            super::parse_cards("Card 1: 1 2 | 2 3\r\nCard 2: 4 | 4\r\n").collect();
+        assert_eq!(crlf.len(), 2);
+        assert_eq!(crlf[1].winning_number_count(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid card:  x1: 1 | 2")]
+    fn test_parse_line_invalid_id() {
+        let _ = super::parse_line("Card x1: 1 | 2");
     }
 }
 
 mod part_1 {
     fn solution(input: &str) -> usize {
         let cards = super::parse_cards(input);
-        cards.map(|c| c.winnings()).sum()
+        cards
+            .map(|c| c.winnings())
+            .sum::<Option<usize>>()
+            .expect("a card is worth more points than fit in a usize")
     }
 
     #[test]
@@ -73,34 +392,347 @@ mod part_1 {
 }
 
 mod part_2 {
-    use crate::Card;
+    use crate::{Card, CopyWindow};
 
     fn solution(input: &str) -> usize {
         let cards: Vec<Card> = super::parse_cards(input).collect();
-        let mut card_copies = vec![1; cards.len()];
+        CopyWindow::default().copies(&cards).iter().sum()
+    }
+
+    #[test]
+    fn test_part_2_example() {
+        assert_eq!(solution(super::EXAMPLE_INPUT), 30);
+    }
+
+    #[test]
+    fn test_part_2() {
+        eprintln!("Solution Day 4 Part 2: [{}]", solution(super::INPUT));
+    }
+}
+
+pub mod cascade {
+    use std::fmt::Write;
+
+    use crate::{Card, CopyWindow};
+
+    #[derive(Debug, Clone, Eq, PartialEq)]
+    pub struct CardCascade {
+        pub id: usize,
+        pub matches: usize,
+        // total instances of this card, the original plus every copy won
+        pub copies: usize,
+        // (card id, copies) for every earlier card that handed out copies of this one
+        pub won_from: Vec<(usize, usize)>,
+        // copies of later cards won by all instances of this card
+        pub contribution: usize,
+    }
+
+    // How the copies of `cards` come about when they are played with `window`.
+    #[must_use]
+    pub fn cascade(cards: &[Card], window: CopyWindow) -> Vec<CardCascade> {
+        let mut cascade: Vec<CardCascade> = cards
+            .iter()
+            .map(|card| CardCascade {
+                id: card.id,
+                matches: card.winning_number_count(),
+                copies: 1,
+                won_from: vec![],
+                contribution: 0,
+            })
+            .collect();
+        let copies = window.play(cards, |i, t, copies| {
+            cascade[t].won_from.push((cards[i].id, copies));
+            cascade[i].contribution += copies;
+        });
+        for (card, copies) in cascade.iter_mut().zip(copies) {
+            card.copies = copies;
+        }
+        cascade
+    }
+
+    #[must_use]
+    pub fn to_text(cascade: &[CardCascade]) -> String {
+        let mut output = String::new();
+        for card in cascade {
+            let _ = write!(
+                output,
+                "Card {}: {} copies (1 original",
+                card.id, card.copies
+            );
+            for (id, copies) in &card.won_from {
+                let _ = write!(output, " + {copies} from card {id}");
+            }
+            let _ = writeln!(
+                output,
+                "), {} matching, contributes {}",
+                card.matches, card.contribution
+            );
+        }
+        output
+    }
+
+    // A Graphviz graph with an edge for every card that won copies of another.
+    #[must_use]
+    pub fn to_dot(cascade: &[CardCascade]) -> String {
+        let mut output = String::from("digraph cascade {\n");
+        for card in cascade {
+            let _ = writeln!(
+                output,
+                "    card{0} [label=\"Card {0}\\n{1} copies\"];",
+                card.id, card.copies
+            );
+        }
+        for card in cascade {
+            for (id, copies) in &card.won_from {
+                let _ = writeln!(
+                    output,
+                    "    card{id} -> card{} [label=\"{copies}\"];",
+                    card.id
+                );
+            }
+        }
+        output.push_str("}\n");
+        output
+    }
+
+    #[test]
+    fn test_cascade_example() {
+        let cards: Vec<Card> = super::parse_cards(super::EXAMPLE_INPUT).collect();
+        let cascade = cascade(&cards, CopyWindow::default());
+        let copies: Vec<usize> = cascade.iter().map(|c| c.copies).collect();
+        assert_eq!(copies, vec![1, 2, 4, 8, 14, 1]);
+        assert_eq!(copies.iter().sum::<usize>(), 30);
+        assert_eq!(cascade[3].won_from, vec![(1, 1), (2, 2), (3, 4)]);
+        assert_eq!(cascade[2].contribution, 8);
+    }
+
+    #[test]
+    fn test_cascade_window_example() {
+        use crate::TableEnd;
+
+        let cards: Vec<Card> = super::parse_cards(super::EXAMPLE_INPUT).collect();
+        let wrap = CopyWindow {
+            offset: 3,
+            table_end: TableEnd::Wrap,
+        };
+        let cascade = cascade(&cards, wrap);
+        let copies: Vec<usize> = cascade.iter().map(|c| c.copies).collect();
+        assert_eq!(copies, wrap.copies(&cards));
+        assert_eq!(cascade[0].won_from, vec![(1, 1), (3, 1), (4, 2)]);
+        assert_eq!(cascade[0].contribution, 4);
+    }
+
+    #[test]
+    fn test_cascade_renderings_example() {
+        let cards: Vec<Card> = super::parse_cards(super::EXAMPLE_INPUT).collect();
+        let cascade = cascade(&cards, CopyWindow::default());
+        let text = to_text(&cascade);
+        assert_eq!(
+            text.lines().nth(4),
+            Some(
+                "Card 5: 14 copies (1 original + 1 from card 1 + 4 from card 3 + 8 from card 4), \
+                 0 matching, contributes 0"
+            )
+        );
+        let dot = to_dot(&cascade);
+        assert!(dot.starts_with("digraph cascade {\n    card1 [label=\"Card 1\\n1 copies\"];"));
+        assert!(dot.contains("    card3 -> card4 [label=\"4\"];\n"));
+        assert!(dot.ends_with("}\n"));
+    }
+}
+
+mod variants {
+    use crate::{Card, CopyWindow, Scoring};
+
+    fn score(input: &str, scoring: &Scoring) -> Option<usize> {
+        super::parse_cards(input).map(|c| c.score(scoring)).sum()
+    }
+
+    fn copies(input: &str, window: CopyWindow) -> Vec<usize> {
+        let cards: Vec<Card> = super::parse_cards(input).collect();
+        window.copies(&cards)
+    }
+
+    #[test]
+    fn test_scoring_example() {
+        // matches per card: 4, 2, 2, 1, 0, 0
+        assert_eq!(score(super::EXAMPLE_INPUT, &Scoring::Doubling), Some(13));
+        assert_eq!(score(super::EXAMPLE_INPUT, &Scoring::Linear), Some(9));
+        assert_eq!(
+            score(super::EXAMPLE_INPUT, &Scoring::Fibonacci),
+            Some(3 + 1 + 1 + 1)
+        );
+        let squared = Scoring::Custom(Box::new(|matches| matches * matches));
+        assert_eq!(score(super::EXAMPLE_INPUT, &squared), Some(16 + 4 + 4 + 1));
+    }
+
+    #[test]
+    fn test_scoring_overflow() {
+        let bits = usize::BITS as usize;
+        assert_eq!(Scoring::Doubling.score(bits), Some(1 << (bits - 1)));
+        assert_eq!(Scoring::Doubling.score(bits + 1), None);
+        assert_eq!(Scoring::Fibonacci.score(50), Some(12_586_269_025));
+        assert_eq!(
+            Scoring::Fibonacci.score(93),
+            Some(12_200_160_415_121_876_738)
+        );
+        assert_eq!(Scoring::Fibonacci.score(94), None);
+        assert_eq!(Scoring::Fibonacci.score(200), None);
+
+        // every number from 0 to 99 on both sides
+        let numbers: Vec<String> = (0..100).map(|n| n.to_string()).collect();
+        let numbers = numbers.join(" ");
+        let input = format!("Card 1: {numbers} | {numbers}");
+        assert_eq!(score(&input, &Scoring::Linear), Some(100));
+        assert_eq!(score(&input, &Scoring::Doubling), None);
+        assert_eq!(score(&input, &Scoring::Fibonacci), None);
+    }
+
+    #[test]
+    fn test_copy_windows_example() {
+        use crate::TableEnd;
+
+        assert_eq!(
+            copies(super::EXAMPLE_INPUT, CopyWindow::default()),
+            vec![1, 2, 4, 8, 14, 1]
+        );
+        let offset = CopyWindow {
+            offset: 2,
+            ..CopyWindow::default()
+        };
+        assert_eq!(copies(super::EXAMPLE_INPUT, offset), vec![1, 1, 2, 3, 5, 7]);
+        let truncate = CopyWindow {
+            offset: 3,
+            table_end: TableEnd::Truncate,
+        };
+        assert_eq!(
+            copies(super::EXAMPLE_INPUT, truncate),
+            vec![1, 1, 1, 2, 3, 4]
+        );
+        let clamp = CopyWindow {
+            offset: 3,
+            table_end: TableEnd::Clamp,
+        };
+        assert_eq!(copies(super::EXAMPLE_INPUT, clamp), vec![1, 1, 1, 2, 3, 8]);
+        let wrap = CopyWindow {
+            offset: 3,
+            table_end: TableEnd::Wrap,
+        };
+        assert_eq!(copies(super::EXAMPLE_INPUT, wrap), vec![5, 1, 1, 2, 3, 4]);
+    }
+}
+
+pub mod validation {
+    use std::collections::BTreeSet;
+
+    use crate::Card;
+
+    #[derive(Debug, Clone, Eq, PartialEq)]
+    pub enum CardError {
+        // card `id` was listed after card `previous`
+        OutOfOrder {
+            id: usize,
+            previous: usize,
+        },
+        DuplicateId(usize),
+        // no card with this id between the lowest and highest id
+        MissingId(usize),
+        DuplicateNumber {
+            id: usize,
+            number: u8,
+        },
+        // card `id` lists a different amount of numbers than the first card
+        LengthMismatch {
+            id: usize,
+            lengths: (usize, usize),
+            expected: (usize, usize),
+        },
+    }
+
+    #[must_use]
+    pub fn validate(cards: &[Card]) -> Vec<CardError> {
+        let mut errors = vec![];
+        let mut seen = BTreeSet::new();
+        let expected = cards.first().map(|card| card.lengths);
         for (i, card) in cards.iter().enumerate() {
-            let win_count = card.winning_number_count();
-            if win_count == 0 {
-                continue;
+            if !seen.insert(card.id) {
+                errors.push(CardError::DuplicateId(card.id));
+            } else if i > 0 && card.id < cards[i - 1].id {
+                errors.push(CardError::OutOfOrder {
+                    id: card.id,
+                    previous: cards[i - 1].id,
+                });
             }
-            let copies = card_copies[i];
-            for j in 1..=win_count {
-                if i + j < card_copies.len() {
-                    card_copies[i + j] += copies;
-                }
+            errors.extend(
+                (0..=u8::MAX)
+                    .filter(|&n| card.duplicates.contains(n))
+                    .map(|number| CardError::DuplicateNumber {
+                        id: card.id,
+                        number,
+                    }),
+            );
+            if let Some(expected) = expected.filter(|&expected| expected != card.lengths) {
+                errors.push(CardError::LengthMismatch {
+                    id: card.id,
+                    lengths: card.lengths,
+                    expected,
+                });
             }
         }
+        if let (Some(&first), Some(&last)) = (seen.first(), seen.last()) {
+            errors.extend(
+                (first..=last)
+                    .filter(|id| !seen.contains(id))
+                    .map(CardError::MissingId),
+            );
+        }
+        errors
+    }
 
-        card_copies.iter().sum()
+    #[test]
+    fn test_validate_inputs() {
+        let cards: Vec<Card> = super::parse_cards(super::EXAMPLE_INPUT).collect();
+        assert_eq!(validate(&cards), vec![]);
+        let cards: Vec<Card> = super::parse_cards(super::INPUT).collect();
+        assert_eq!(validate(&cards), vec![]);
     }
 
     #[test]
-    fn test_part_2_example() {
-        assert_eq!(solution(super::EXAMPLE_INPUT), 30);
+    fn test_validate_errors() {
+        let input = "Card 2: 1 2 | 3 4 5
+Card 1: 1 1 | 3 4 5
+Card 4: 1 2 3 | 3 4 4
+Card 4: 1 2 | 3 4 5";
+        let cards: Vec<Card> = super::parse_cards(input).collect();
+        assert_eq!(
+            validate(&cards),
+            vec![
+                CardError::OutOfOrder { id: 1, previous: 2 },
+                CardError::DuplicateNumber { id: 1, number: 1 },
+                CardError::DuplicateNumber { id: 4, number: 4 },
+                CardError::LengthMismatch {
+                    id: 4,
+                    lengths: (3, 3),
+                    expected: (2, 3),
+                },
+                CardError::DuplicateId(4),
+                CardError::MissingId(3),
+            ]
+        );
     }
 
     #[test]
-    fn test_part_2() {
-        eprintln!("Solution Day 4 Part 2: [{}]", solution(super::INPUT));
+    fn test_copies_by_id() {
+        use crate::CopyWindow;
+
+        // the example cards shuffled and with card 3 removed
+        let input = "Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11
+Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36";
+        let cards: Vec<Card> = super::parse_cards(input).collect();
+        // card 1 wins 2, 3, 4 and 5, card 2 wins 3 and 4, card 4 wins 5
+        assert_eq!(CopyWindow::default().copies(&cards), vec![4, 1, 1, 2, 6]);
     }
 }
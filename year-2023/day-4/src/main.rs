@@ -0,0 +1,32 @@
+use std::process::exit;
+use std::{env, fs};
+
+use day_4::cascade::{cascade, to_dot, to_text};
+use day_4::{parse_cards, Card, CopyWindow};
+
+// Explains how the copies of a table of scratchcards come about:
+//   day-4 <cards> [--dot]
+// With `--dot` the cascade is printed as a Graphviz graph instead of text.
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (path, dot) = match &args[..] {
+        [path] => (path, false),
+        [path, flag] if flag == "--dot" => (path, true),
+        _ => {
+            eprintln!("usage: day-4 <cards> [--dot]");
+            exit(2);
+        }
+    };
+    let input = fs::read_to_string(path).unwrap_or_else(|e| {
+        eprintln!("could not read {path}: {e}");
+        exit(1);
+    });
+
+    let cards: Vec<Card> = parse_cards(&input).collect();
+    let cascade = cascade(&cards, CopyWindow::default());
+    if dot {
+        print!("{}", to_dot(&cascade));
+    } else {
+        print!("{}", to_text(&cascade));
+    }
+}
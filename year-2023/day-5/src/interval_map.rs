@@ -0,0 +1,248 @@
+use std::iter::Sum;
+use std::ops::{Add, Range, Sub};
+
+use crate::range_set::RangeSet;
+
+// A range of source values that is shifted so `source.start` lands on `target`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment<T> {
+    pub source: Range<T>,
+    pub target: T,
+}
+
+impl<T> Segment<T>
+where
+    T: Copy + Ord + Add<Output = T> + Sub<Output = T>,
+{
+    fn map(&self, value: T) -> T {
+        self.target + (value - self.source.start)
+    }
+}
+
+// Piecewise offset map, values outside of every segment map to themselves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntervalMap<T> {
+    // sorted by source start, non-empty and non-overlapping
+    segments: Vec<Segment<T>>,
+}
+
+impl<T> Default for IntervalMap<T> {
+    fn default() -> Self {
+        IntervalMap { segments: vec![] }
+    }
+}
+
+impl<T> IntervalMap<T>
+where
+    T: Copy + Ord + Add<Output = T> + Sub<Output = T> + Sum,
+{
+    // Overlapping segments are trimmed, the one listed first wins the overlap.
+    #[must_use]
+    pub fn new(segments: Vec<Segment<T>>) -> Self {
+        let mut covered = RangeSet::new(vec![]);
+        let mut trimmed = vec![];
+        for segment in segments {
+            let sources = RangeSet::new(vec![segment.source.clone()]);
+            for piece in sources.difference(&covered).ranges() {
+                trimmed.push(Segment {
+                    source: piece.clone(),
+                    target: segment.map(piece.start),
+                });
+            }
+            covered = covered.union(&sources);
+        }
+        trimmed.sort_by_key(|s| s.source.start);
+        IntervalMap { segments: trimmed }
+    }
+
+    #[must_use]
+    pub fn segments(&self) -> &[Segment<T>] {
+        &self.segments
+    }
+
+    #[must_use]
+    pub fn get(&self, value: T) -> T {
+        let index = self.segments.partition_point(|s| s.source.start <= value);
+        match index.checked_sub(1).map(|i| &self.segments[i]) {
+            Some(segment) if value < segment.source.end => segment.map(value),
+            _ => value,
+        }
+    }
+
+    // The image of `range`, one output range for every segment or unmapped gap it touches.
+    #[must_use]
+    pub fn get_range(&self, range: Range<T>) -> Vec<Range<T>> {
+        self.pieces(range)
+            .into_iter()
+            .map(|(source, target)| target..target + (source.end - source.start))
+            .collect()
+    }
+
+    // A single map equivalent to applying `self` and then `next`.
+    #[must_use]
+    pub fn compose(&self, next: &IntervalMap<T>) -> IntervalMap<T> {
+        // outside the span of both maps both are the identity, and so is the composition
+        let start = [self.segments.first(), next.segments.first()]
+            .into_iter()
+            .flatten()
+            .map(|s| s.source.start)
+            .min();
+        let end = [self.segments.last(), next.segments.last()]
+            .into_iter()
+            .flatten()
+            .map(|s| s.source.end)
+            .max();
+        let (Some(start), Some(end)) = (start, end) else {
+            return IntervalMap::default();
+        };
+
+        let mut segments: Vec<Segment<T>> = vec![];
+        for (source, middle) in self.pieces(start..end) {
+            let len = source.end - source.start;
+            for (piece, target) in next.pieces(middle..middle + len) {
+                let piece_start = source.start + (piece.start - middle);
+                let piece = piece_start..piece_start + (piece.end - piece.start);
+                if piece.start == target {
+                    continue;
+                }
+                match segments.last_mut() {
+                    Some(last)
+                        if last.source.end == piece.start && last.map(piece.start) == target =>
+                    {
+                        last.source.end = piece.end;
+                    }
+                    _ => segments.push(Segment {
+                        source: piece,
+                        target,
+                    }),
+                }
+            }
+        }
+        IntervalMap { segments }
+    }
+
+    // Splits `range` into pieces that are each shifted by a single segment or left unmapped,
+    // paired with the value the start of the piece maps to.
+    fn pieces(&self, range: Range<T>) -> Vec<(Range<T>, T)> {
+        let mut pieces = vec![];
+        let mut start = range.start;
+        let first = self.segments.partition_point(|s| s.source.end <= start);
+        for segment in &self.segments[first..] {
+            if segment.source.start >= range.end {
+                break;
+            }
+            if start < segment.source.start {
+                pieces.push((start..segment.source.start, start));
+                start = segment.source.start;
+            }
+            let end = segment.source.end.min(range.end);
+            pieces.push((start..end, segment.map(start)));
+            start = end;
+        }
+        if start < range.end {
+            pieces.push((start..range.end, start));
+        }
+        pieces
+    }
+}
+
+#[test]
+fn test_get() {
+    let map = IntervalMap::new(vec![
+        Segment {
+            source: 98..100,
+            target: 50,
+        },
+        Segment {
+            source: 50..98,
+            target: 52,
+        },
+    ]);
+    assert_eq!(map.get(0), 0);
+    assert_eq!(map.get(50), 52);
+    assert_eq!(map.get(97), 99);
+    assert_eq!(map.get(98), 50);
+    assert_eq!(map.get(100), 100);
+    assert_eq!(
+        map.get_range(45..105),
+        vec![45..50, 52..100, 50..52, 100..105]
+    );
+}
+
+#[test]
+fn test_overlapping_segments() {
+    let map = IntervalMap::new(vec![
+        Segment {
+            source: 0..10,
+            target: 100,
+        },
+        Segment {
+            source: 2..3,
+            target: 200,
+        },
+        Segment {
+            source: 8..15,
+            target: 300,
+        },
+    ]);
+    assert_eq!(
+        map.segments(),
+        &[
+            Segment {
+                source: 0..10,
+                target: 100,
+            },
+            Segment {
+                source: 10..15,
+                target: 302,
+            },
+        ]
+    );
+    assert_eq!(map.get(2), 102);
+    assert_eq!(map.get(5), 105);
+    assert_eq!(map.get(12), 304);
+    assert_eq!(map.get_range(0..20), vec![100..110, 302..307, 15..20]);
+}
+
+#[test]
+fn test_compose() {
+    let first = IntervalMap::new(vec![Segment {
+        source: 10..20,
+        target: 100,
+    }]);
+    let second = IntervalMap::new(vec![
+        Segment {
+            source: 105..110,
+            target: 0,
+        },
+        Segment {
+            source: 15..25,
+            target: 115,
+        },
+    ]);
+    let composed = first.compose(&second);
+    assert_eq!(
+        composed.segments(),
+        &[
+            Segment {
+                source: 10..15,
+                target: 100,
+            },
+            Segment {
+                source: 15..20,
+                target: 0,
+            },
+            Segment {
+                source: 20..25,
+                target: 120,
+            },
+            Segment {
+                source: 105..110,
+                target: 0,
+            },
+        ]
+    );
+    for value in 0..200 {
+        assert_eq!(composed.get(value), second.get(first.get(value)));
+    }
+}
@@ -1,6 +1,15 @@
 use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
 use std::ops::Range;
 
+use crate::interval_map::{IntervalMap, Segment};
+use crate::range_set::RangeSet;
+
+pub mod interval_map;
+pub mod range_set;
+pub mod trace;
+
 const INPUT: &str = include_str!("../puzzle_input");
 const EXAMPLE_INPUT: &str = "seeds: 79 14 55 13
 
@@ -36,120 +45,438 @@ humidity-to-location map:
 60 56 37
 56 93 4";
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
-enum Category {
-    SeedToSoil,
-    SoilToFertilizer,
-    FertilizerToWater,
-    WaterToLight,
-    LightToTemperature,
-    TemperatureToHumidity,
-    HumidityToLocation,
+// A map between two categories, named after its `source-to-target map:` header.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+struct Category<'a> {
+    source: &'a str,
+    target: &'a str,
 }
 
-impl Category {
-    fn from_str(value: &str) -> Self {
-        match value {
-            "seed-to-soil" => Category::SeedToSoil,
-            "soil-to-fertilizer" => Category::SoilToFertilizer,
-            "fertilizer-to-water" => Category::FertilizerToWater,
-            "water-to-light" => Category::WaterToLight,
-            "light-to-temperature" => Category::LightToTemperature,
-            "temperature-to-humidity" => Category::TemperatureToHumidity,
-            "humidity-to-location" => Category::HumidityToLocation,
-            _ => panic!("invalid category: {value}"),
-        }
+impl<'a> Category<'a> {
+    fn from_str(value: &'a str) -> Self {
+        let Some((source, target)) = value.split_once("-to-") else {
+            panic!("invalid category: {value}");
+        };
+        Category { source, target }
+    }
+}
+
+impl Display for Category<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-to-{}", self.source, self.target)
     }
+}
+
+type Mappings<'a> = HashMap<Category<'a>, CategoryRanges>;
 
-    fn next(self) -> Option<Self> {
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum ChainError {
+    NoPath {
+        from: String,
+        to: String,
+    },
+    // several chains of maps lead from `from` to `to`, they split up after `category`
+    Branches {
+        from: String,
+        to: String,
+        category: String,
+        targets: Vec<String>,
+    },
+}
+
+impl Display for ChainError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
-            Category::SeedToSoil => Some(Self::SoilToFertilizer),
-            Category::SoilToFertilizer => Some(Self::FertilizerToWater),
-            Category::FertilizerToWater => Some(Self::WaterToLight),
-            Category::WaterToLight => Some(Self::LightToTemperature),
-            Category::LightToTemperature => Some(Self::TemperatureToHumidity),
-            Category::TemperatureToHumidity => Some(Self::HumidityToLocation),
-            Category::HumidityToLocation => None,
+            ChainError::NoPath { from, to } => write!(f, "no chain of maps from {from} to {to}"),
+            ChainError::Branches {
+                from,
+                to,
+                category,
+                targets,
+            } => write!(
+                f,
+                "the chain of maps from {from} to {to} branches at {category} into {}",
+                targets.join(", ")
+            ),
         }
     }
 }
 
+impl Error for ChainError {}
+
+// The only chain of maps leading from category `from` to category `to`.
+fn find_chain<'a>(
+    mappings: &Mappings<'a>,
+    from: &str,
+    to: &str,
+) -> Result<Vec<Category<'a>>, ChainError> {
+    let mut paths = vec![];
+    collect_chains(mappings, from, to, &mut vec![], &mut paths);
+    match paths.len() {
+        0 => Err(ChainError::NoPath {
+            from: from.to_owned(),
+            to: to.to_owned(),
+        }),
+        1 => Ok(paths.pop().unwrap()),
+        _ => {
+            // paths end when they reach `to`, so none of them is a prefix of another
+            let split = (0..paths[0].len())
+                .find(|&i| paths.iter().any(|path| path[i] != paths[0][i]))
+                .unwrap();
+            let mut targets: Vec<String> = paths
+                .iter()
+                .map(|path| path[split].target.to_owned())
+                .collect();
+            targets.dedup();
+            Err(ChainError::Branches {
+                from: from.to_owned(),
+                to: to.to_owned(),
+                category: paths[0][split].source.to_owned(),
+                targets,
+            })
+        }
+    }
+}
+
+// Depth-first search for chains from `from` to `to`, which gives up once it has found two:
+// that is enough to tell there is no single chain, and listing them all takes exponential time.
+fn collect_chains<'a>(
+    mappings: &Mappings<'a>,
+    from: &str,
+    to: &str,
+    path: &mut Vec<Category<'a>>,
+    paths: &mut Vec<Vec<Category<'a>>>,
+) {
+    if from == to {
+        paths.push(path.clone());
+        return;
+    }
+    let mut next: Vec<Category<'a>> = mappings
+        .keys()
+        .filter(|c| c.source == from && c.target != from)
+        .filter(|c| !path.iter().any(|p| p.source == c.target))
+        .copied()
+        .collect();
+    next.sort();
+    for category in next {
+        if paths.len() > 1 {
+            return;
+        }
+        path.push(category);
+        collect_chains(mappings, category.target, to, path, paths);
+        path.pop();
+    }
+}
+
 #[derive(Debug)]
 struct CategoryRanges(Vec<CategoryRange>);
 
+// How to build a map out of ranges that overlap or are empty.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Precedence {
+    Reject,
+    // where source ranges overlap the range listed first wins, empty ranges are dropped
+    FirstListed,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum RangeIssue {
+    OverlappingSources { lines: (usize, usize) },
+    // two ranges map onto the same numbers, so the map is not injective
+    OverlappingTargets { lines: (usize, usize) },
+    Empty { line: usize },
+    Overflow { line: usize },
+}
+
+impl Display for RangeIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RangeIssue::OverlappingSources { lines: (a, b) } => {
+                write!(f, "lines {a} and {b} have overlapping source ranges")
+            }
+            RangeIssue::OverlappingTargets { lines: (a, b) } => {
+                write!(f, "lines {a} and {b} have overlapping target ranges")
+            }
+            RangeIssue::Empty { line } => write!(f, "line {line} has an empty range"),
+            RangeIssue::Overflow { line } => write!(f, "line {line} does not fit in a u64"),
+        }
+    }
+}
+
 impl CategoryRanges {
-    fn new(mut input: Vec<CategoryRange>) -> Self {
-        input.sort_by(|a, b| a.source.cmp(&b.source).then(a.length.cmp(&b.length)));
-        CategoryRanges(input)
+    // Overflowing ranges are always rejected, other issues only when `precedence` rejects them.
+    fn new(input: Vec<CategoryRange>, precedence: Precedence) -> Result<Self, Vec<RangeIssue>> {
+        let issues: Vec<RangeIssue> = find_issues(&input)
+            .into_iter()
+            .filter(|issue| {
+                precedence == Precedence::Reject || matches!(issue, RangeIssue::Overflow { .. })
+            })
+            .collect();
+        if !issues.is_empty() {
+            return Err(issues);
+        }
+
+        let mut ranges = match precedence {
+            Precedence::Reject => input,
+            Precedence::FirstListed => remove_overlaps(input),
+        };
+        ranges.sort_by(|a, b| a.source.cmp(&b.source).then(a.length.cmp(&b.length)));
+        Ok(CategoryRanges(ranges))
     }
 
     fn resolve_number(&self, input: u64) -> u64 {
-        match self.0.binary_search_by(|r| r.source.cmp(&input)) {
-            Ok(index) => {
-                let r = &self.0[index];
-                r.target + input - r.source
+        let index = self.0.partition_point(|r| r.source <= input);
+        match index.checked_sub(1).map(|i| &self.0[i]) {
+            Some(r) if r.sources().contains(&input.into()) => narrow(r.map(input.into())),
+            _ => input,
+        }
+    }
+
+    // Every input that resolves to `output`, a mapping can send several inputs to the same output.
+    fn inverse_number(&self, output: u64) -> Vec<u64> {
+        let number = u128::from(output);
+        let mut inputs: Vec<u64> = self
+            .0
+            .iter()
+            .filter(|r| r.targets().contains(&number))
+            .map(|r| narrow(r.unmap(number)))
+            .collect();
+        if !self.0.iter().any(|r| r.sources().contains(&number)) {
+            // unmapped numbers resolve to themselves
+            inputs.push(output);
+        }
+        inputs
+    }
+
+    // Every range of inputs that resolves into `output`.
+    fn inverse_numbers(&self, output: Range<u128>) -> Vec<Range<u128>> {
+        let mut inputs = vec![];
+        for r in &self.0 {
+            let targets = r.targets();
+            let start = output.start.max(targets.start);
+            let end = output.end.min(targets.end);
+            if start < end {
+                inputs.push(r.unmap(start)..r.unmap(end));
             }
-            Err(0) => input,
-            Err(max_index) => {
-                let range = &self.0[max_index - 1];
-                if input < range.source + range.length {
-                    range.target + input - range.source
-                } else {
-                    input
-                }
+        }
+
+        // unmapped numbers resolve to themselves
+        let mut start = output.start;
+        for r in &self.0 {
+            if start >= output.end {
+                break;
             }
+            let sources = r.sources();
+            if sources.start > start {
+                inputs.push(start..sources.start.min(output.end));
+            }
+            start = start.max(sources.end);
+        }
+        if start < output.end {
+            inputs.push(start..output.end);
         }
+        inputs
     }
 
-    fn resolve_numbers(&self, input: Range<u64>) -> Vec<Range<u64>> {
-        let mapping_iter = self.0.iter().filter(|&r| input.start < r.source + r.length);
+    fn to_interval_map(&self) -> IntervalMap<u128> {
+        IntervalMap::new(
+            self.0
+                .iter()
+                .map(|r| Segment {
+                    source: r.sources(),
+                    target: r.target.into(),
+                })
+                .collect(),
+        )
+    }
 
-        let mut range = input.clone();
+    fn resolve_numbers(&self, input: Range<u128>) -> Vec<Range<u128>> {
+        self.trace_numbers(input)
+            .into_iter()
+            .map(|(range, _)| range)
+            .collect()
+    }
+
+    // Like `resolve_numbers`, but keeps the line that mapped every output range, `None` for the
+    // ranges that passed through unmapped.
+    fn trace_numbers(&self, input: Range<u128>) -> Vec<(Range<u128>, Option<usize>)> {
+        if input.is_empty() {
+            return vec![];
+        }
         let mut output = vec![];
-        for mapping in mapping_iter {
-            // no matching mapping
-            if input.start < mapping.source {
-                let max = range.end.min(mapping.source);
-                output.push(range.start..max);
-                range = mapping.source..range.end;
-                if range.is_empty() {
-                    return output;
-                }
+        let mut start = input.start;
+        let first = self.0.partition_point(|r| r.sources().end <= start);
+        for r in &self.0[first..] {
+            let sources = r.sources();
+            if sources.start >= input.end {
+                break;
             }
-
-            // partial mapping
-            if range.end < mapping.source + mapping.length {
-                let min = mapping.target + range.start - mapping.source;
-                let max = mapping.target + range.end - mapping.source;
-                output.push(min..max);
-                return output;
+            // no matching mapping
+            if start < sources.start {
+                output.push((start..sources.start, None));
+                start = sources.start;
             }
-
-            let max_number = range.end.min(mapping.source + mapping.length);
-            let max = mapping.target + max_number - mapping.source;
-            let min = mapping.target + range.start - mapping.source;
-            output.push(min..max);
-            range = max_number..range.end;
+            let end = sources.end.min(input.end);
+            output.push((r.map(start)..r.map(end), Some(r.line)));
+            start = end;
         }
-        if !range.is_empty() {
-            output.push(range);
+        if start < input.end {
+            output.push((start..input.end, None));
         }
         output
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
 struct CategoryRange {
     source: u64,
     target: u64,
     length: u64,
+    // line in the almanac the range was listed on
+    line: usize,
 }
 
-fn parse_input(input: &str) -> (Vec<u64>, HashMap<Category, CategoryRanges>) {
+// One past `u64::MAX`, the end of every range that reaches the top of the domain.
+const DOMAIN_END: u128 = 1 << 64;
+
+// Numbers are u64, but range ends are u128 so a half-open range can include `u64::MAX`.
+// Sums of two u64 always fit in a u128, narrowing back to u64 is checked.
+impl CategoryRange {
+    fn sources(&self) -> Range<u128> {
+        u128::from(self.source)..u128::from(self.source) + u128::from(self.length)
+    }
+
+    fn targets(&self) -> Range<u128> {
+        u128::from(self.target)..u128::from(self.target) + u128::from(self.length)
+    }
+
+    // Maps a number from (or a range end at) the source range to the target range.
+    fn map(&self, number: u128) -> u128 {
+        number - u128::from(self.source) + u128::from(self.target)
+    }
+
+    fn unmap(&self, number: u128) -> u128 {
+        number - u128::from(self.target) + u128::from(self.source)
+    }
+}
+
+fn narrow(number: u128) -> u64 {
+    u64::try_from(number).expect("number outside of the u64 domain")
+}
+
+fn find_issues(ranges: &[CategoryRange]) -> Vec<RangeIssue> {
+    let mut issues = vec![];
+    let mut valid: Vec<&CategoryRange> = vec![];
+    for r in ranges {
+        if r.sources().end > DOMAIN_END || r.targets().end > DOMAIN_END {
+            issues.push(RangeIssue::Overflow { line: r.line });
+        } else if r.length == 0 {
+            issues.push(RangeIssue::Empty { line: r.line });
+        } else {
+            valid.push(r);
+        }
+    }
+    for (i, a) in valid.iter().enumerate() {
+        for b in &valid[i + 1..] {
+            let lines = (a.line, b.line);
+            let (a_sources, b_sources) = (a.sources(), b.sources());
+            if a_sources.start < b_sources.end && b_sources.start < a_sources.end {
+                issues.push(RangeIssue::OverlappingSources { lines });
+            }
+            let (a_targets, b_targets) = (a.targets(), b.targets());
+            if a_targets.start < b_targets.end && b_targets.start < a_targets.end {
+                issues.push(RangeIssue::OverlappingTargets { lines });
+            }
+        }
+    }
+    issues
+}
+
+// Trims every range down to the sources not already covered by a range listed before it.
+fn remove_overlaps(ranges: Vec<CategoryRange>) -> Vec<CategoryRange> {
+    let mut covered = RangeSet::default();
+    let mut output = vec![];
+    for r in ranges {
+        let sources: RangeSet<u128> = std::iter::once(r.sources()).collect();
+        for piece in sources.difference(&covered).ranges() {
+            output.push(CategoryRange {
+                source: narrow(piece.start),
+                target: narrow(r.map(piece.start)),
+                length: narrow(piece.end - piece.start),
+                line: r.line,
+            });
+        }
+        covered = covered.union(&sources);
+    }
+    output
+}
+
+fn parse_input(input: &str) -> (Vec<u64>, Mappings<'_>) {
+    parse_input_with(input, Precedence::Reject)
+}
+
+fn parse_input_with(input: &str, precedence: Precedence) -> (Vec<u64>, Mappings<'_>) {
     let mut parts = input.split("\n\n");
-    let seeds = parse_seeds(parts.next().unwrap());
-    let map = parts.map(parse_map).collect();
-    (seeds, map)
+    let seeds = parts.next().unwrap();
+    // line number of the next map header
+    let mut line = seeds.lines().count() + 2;
+    let mut map = HashMap::new();
+    let mut headers = HashMap::new();
+    for part in parts {
+        let (category, ranges) = parse_map(part, line, precedence);
+        if let Some(first) = headers.insert(category, line) {
+            panic!("duplicate {category} map at line {line}, first defined at line {first}");
+        }
+        map.insert(category, ranges);
+        line += part.lines().count() + 1;
+    }
+    (parse_seeds(seeds), map)
+}
+
+// The chain of maps from category `from` to category `to` as a single map.
+fn chain_map(mappings: &Mappings, from: &str, to: &str) -> Result<IntervalMap<u128>, ChainError> {
+    let chain = find_chain(mappings, from, to)?;
+    Ok(chain.iter().fold(IntervalMap::default(), |map, category| {
+        map.compose(&mappings[category].to_interval_map())
+    }))
+}
+
+fn seed_to_location(mappings: &Mappings) -> IntervalMap<u128> {
+    chain_map(mappings, "seed", "location").unwrap()
+}
+
+// Every seed that ends up at `location`.
+fn location_to_seeds(mappings: &Mappings, location: u64) -> Vec<u64> {
+    let mut numbers = vec![location];
+    for category in find_chain(mappings, "seed", "location")
+        .unwrap()
+        .iter()
+        .rev()
+    {
+        let mapping = &mappings[category];
+        numbers = numbers
+            .into_iter()
+            .flat_map(|number| mapping.inverse_number(number))
+            .collect();
+    }
+    numbers
+}
+
+// Every range of seeds that ends up inside `locations`.
+fn locations_to_seeds(mappings: &Mappings, locations: Range<u128>) -> Vec<Range<u128>> {
+    let mut ranges = vec![locations];
+    for category in find_chain(mappings, "seed", "location")
+        .unwrap()
+        .iter()
+        .rev()
+    {
+        let mapping = &mappings[category];
+        ranges = ranges
+            .into_iter()
+            .flat_map(|range| mapping.inverse_numbers(range))
+            .collect();
+    }
+    ranges
 }
 
 fn parse_seeds(input: &str) -> Vec<u64> {
@@ -160,15 +487,22 @@ fn parse_seeds(input: &str) -> Vec<u64> {
         .collect()
 }
 
-fn parse_map(input: &str) -> (Category, CategoryRanges) {
+fn parse_map(input: &str, line: usize, precedence: Precedence) -> (Category<'_>, CategoryRanges) {
     let (key, ranges) = input.split_once("map:\n").unwrap();
     let category = Category::from_str(key.trim());
-    let ranges = ranges.lines().map(parse_range).collect();
-    // eprintln!("{category:?}: {ranges:?}");
-    (category, CategoryRanges::new(ranges))
+    let ranges = ranges
+        .lines()
+        .enumerate()
+        .map(|(i, range)| parse_range(range, line + 1 + i))
+        .collect();
+    let ranges = CategoryRanges::new(ranges, precedence).unwrap_or_else(|issues| {
+        let issues: Vec<String> = issues.iter().map(ToString::to_string).collect();
+        panic!("invalid {category} map: {}", issues.join(", "))
+    });
+    (category, ranges)
 }
 
-fn parse_range(input: &str) -> CategoryRange {
+fn parse_range(input: &str, line: usize) -> CategoryRange {
     let parts = input.split_whitespace().collect::<Vec<_>>();
     let [target, source, length] = parts[..] else {
         panic!("invalid range: {input}");
@@ -177,35 +511,25 @@ fn parse_range(input: &str) -> CategoryRange {
         source: source.parse().unwrap(),
         target: target.parse().unwrap(),
         length: length.parse().unwrap(),
+        line,
     }
 }
 
 mod part_1 {
-    use std::collections::HashMap;
-
-    use crate::{Category, CategoryRanges};
+    use crate::Mappings;
 
     fn solution(input: &str) -> u64 {
         let (seeds, mappings) = super::parse_input(input);
-        seeds
-            .iter()
-            .copied()
-            .map(|seeds| resolve_location(&mappings, seeds))
-            .min()
-            .unwrap()
+        let chain = super::seed_to_location(&mappings);
+        let location = seeds.iter().map(|&seed| chain.get(seed.into())).min();
+        super::narrow(location.unwrap())
     }
 
-    fn resolve_location(mappings: &HashMap<Category, CategoryRanges>, seed: u64) -> u64 {
-        let mut category = Category::SeedToSoil;
-        let mut number = seed;
-        loop {
-            number = mappings.get(&category).unwrap().resolve_number(number);
-
-            match category.next() {
-                Some(c) => category = c,
-                None => return number,
-            }
-        }
+    fn resolve_location(mappings: &Mappings, seed: u64) -> u64 {
+        let chain = super::find_chain(mappings, "seed", "location").unwrap();
+        chain.iter().fold(seed, |number, category| {
+            mappings[category].resolve_number(number)
+        })
     }
 
     #[test]
@@ -214,6 +538,18 @@ mod part_1 {
         assert_eq!(solution(input), 35);
     }
 
+    #[test]
+    fn test_chain_matches_stages() {
+        for input in [super::EXAMPLE_INPUT, super::INPUT] {
+            let (seeds, mappings) = super::parse_input(input);
+            let chain = super::seed_to_location(&mappings);
+            for seed in seeds.iter().flat_map(|&seed| [seed, seed + 1, seed * 3]) {
+                let location = resolve_location(&mappings, seed);
+                assert_eq!(chain.get(seed.into()), location.into());
+            }
+        }
+    }
+
     #[test]
     fn test_parse_input() {
         eprintln!("Solution Day 5 Part 1: [{}]", solution(super::INPUT));
@@ -221,42 +557,40 @@ mod part_1 {
 }
 
 mod part_2 {
-    use std::collections::HashMap;
-    use std::ops::Range;
-
-    use crate::{Category, CategoryRanges};
+    use crate::range_set::RangeSet;
+    use crate::Mappings;
 
     fn solution(input: &str) -> u64 {
         let (seeds, mappings) = super::parse_input(input);
-        let mut ranges = Vec::with_capacity(seeds.len() / 2);
-        for i in (0..seeds.len()).step_by(2) {
-            let range_start = seeds[i];
-            let range_end = range_start + seeds[i + 1];
-            let seed_range = range_start..range_end;
-            ranges.push(seed_range);
-        }
-        let location_ranges = resolve_location(&mappings, ranges);
-        location_ranges.into_iter().flatten().min().unwrap()
+        let seeds = seed_ranges(&seeds);
+        let chain = super::seed_to_location(&mappings);
+        let location = seeds
+            .ranges()
+            .iter()
+            .flat_map(|range| chain.get_range(range.clone()))
+            .map(|range| range.start)
+            .min();
+        super::narrow(location.unwrap())
+    }
+
+    fn seed_ranges(seeds: &[u64]) -> RangeSet<u128> {
+        seeds
+            .chunks_exact(2)
+            .map(|pair| u128::from(pair[0])..u128::from(pair[0]) + u128::from(pair[1]))
+            .collect()
     }
 
-    fn resolve_location(
-        mappings: &HashMap<Category, CategoryRanges>,
-        seeds: Vec<Range<u64>>,
-    ) -> Vec<Range<u64>> {
-        let mut category = Category::SeedToSoil;
+    fn resolve_location(mappings: &Mappings, seeds: RangeSet<u128>) -> RangeSet<u128> {
         let mut ranges = seeds;
-        loop {
-            let mapping = mappings.get(&category).unwrap();
+        for category in super::find_chain(mappings, "seed", "location").unwrap() {
+            let mapping = &mappings[&category];
             ranges = ranges
-                .into_iter()
-                .flat_map(|range| mapping.resolve_numbers(range))
+                .ranges()
+                .iter()
+                .flat_map(|range| mapping.resolve_numbers(range.clone()))
                 .collect();
-
-            match category.next() {
-                Some(c) => category = c,
-                None => return ranges,
-            }
         }
+        ranges
     }
 
     #[test]
@@ -265,8 +599,435 @@ mod part_2 {
         assert_eq!(solution(input), 46);
     }
 
+    #[test]
+    fn test_chain_matches_stages() {
+        let (seeds, mappings) = super::parse_input(super::INPUT);
+        let chain = super::seed_to_location(&mappings);
+        let seeds = seed_ranges(&seeds);
+        let expected = resolve_location(&mappings, seeds.clone());
+        let actual: RangeSet<u128> = seeds
+            .ranges()
+            .iter()
+            .flat_map(|range| chain.get_range(range.clone()))
+            .collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_normalized_stages_are_smaller() {
+        let (seeds, mappings) = super::parse_input(super::INPUT);
+        let seeds = seed_ranges(&seeds);
+        let mut unmerged: Vec<std::ops::Range<u128>> = seeds.ranges().to_vec();
+        let mut merged = seeds;
+        for category in super::find_chain(&mappings, "seed", "location").unwrap() {
+            let mapping = &mappings[&category];
+            unmerged = unmerged
+                .into_iter()
+                .flat_map(|range| mapping.resolve_numbers(range))
+                .collect();
+            merged = merged
+                .ranges()
+                .iter()
+                .flat_map(|range| mapping.resolve_numbers(range.clone()))
+                .collect();
+            assert!(merged.len() <= unmerged.len());
+            assert_eq!(merged, RangeSet::new(unmerged.clone()));
+        }
+        assert!(merged.len() < unmerged.len());
+    }
+
     #[test]
     fn test_parse_input() {
         eprintln!("Solution Day 5 Part 2: [{}]", solution(super::INPUT));
     }
 }
+
+mod inverse {
+    use std::ops::Range;
+
+    fn contains(ranges: &[Range<u128>], range: &Range<u128>) -> bool {
+        // ranges may be split up at stage boundaries, so check number by number
+        range.clone().all(|n| ranges.iter().any(|r| r.contains(&n)))
+    }
+
+    #[test]
+    fn test_inverse_stages_example() {
+        let (_, mappings) = super::parse_input(super::EXAMPLE_INPUT);
+        for mapping in mappings.values() {
+            for number in 0..120 {
+                let output = mapping.resolve_number(number);
+                assert!(mapping.inverse_number(output).contains(&number));
+                let output = u128::from(output);
+                let inverse = mapping.inverse_numbers(output..output + 1);
+                let number = u128::from(number);
+                assert!(contains(&inverse, &(number..number + 1)));
+            }
+            for range in [0..10, 10..60, 45..105, 90..200] {
+                let outputs = mapping.resolve_numbers(range.clone());
+                let inputs: Vec<Range<u128>> = outputs
+                    .into_iter()
+                    .flat_map(|output| mapping.inverse_numbers(output))
+                    .collect();
+                assert!(contains(&inputs, &range));
+            }
+        }
+    }
+
+    #[test]
+    fn test_location_to_seeds_example() {
+        let (seeds, mappings) = super::parse_input(super::EXAMPLE_INPUT);
+        let chain = super::seed_to_location(&mappings);
+        for seed in seeds {
+            let location = super::narrow(chain.get(seed.into()));
+            assert!(super::location_to_seeds(&mappings, location).contains(&seed));
+        }
+        // the seeds behind the answers to both parts
+        assert!(super::location_to_seeds(&mappings, 35).contains(&13));
+        assert!(super::location_to_seeds(&mappings, 46).contains(&82));
+        let seeds = super::locations_to_seeds(&mappings, 0..47);
+        assert!(contains(&seeds, &(82..83)));
+        assert!(!contains(&seeds, &(79..80)));
+    }
+
+    #[test]
+    fn test_location_to_seeds() {
+        let (seeds, mappings) = super::parse_input(super::INPUT);
+        let chain = super::seed_to_location(&mappings);
+        for seed in seeds {
+            let location = chain.get(seed.into());
+            assert!(super::location_to_seeds(&mappings, super::narrow(location)).contains(&seed));
+            let ranges = super::locations_to_seeds(&mappings, location..location + 1);
+            let seed = u128::from(seed);
+            assert!(contains(&ranges, &(seed..seed + 1)));
+        }
+    }
+}
+
+mod chain {
+    const BRANCHING_INPUT: &str = "seeds: 1 2 3
+
+seed-to-soil map:
+10 0 5
+
+seed-to-water map:
+20 0 5
+
+soil-to-water map:
+0 10 5
+
+water-to-location map:
+100 0 50";
+
+    #[test]
+    fn test_chain_between_any_categories() {
+        let (_, mappings) = super::parse_input(super::EXAMPLE_INPUT);
+        let chain = super::find_chain(&mappings, "soil", "light").unwrap();
+        let names: Vec<String> = chain.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            names,
+            vec![
+                "soil-to-fertilizer",
+                "fertilizer-to-water",
+                "water-to-light"
+            ]
+        );
+        let map = super::chain_map(&mappings, "soil", "light").unwrap();
+        let fertilizer = mappings[&chain[0]].resolve_number(81);
+        let water = mappings[&chain[1]].resolve_number(fertilizer);
+        let light = mappings[&chain[2]].resolve_number(water);
+        assert_eq!(map.get(81), light.into());
+        assert_eq!(
+            super::chain_map(&mappings, "seed", "seed").unwrap().get(7),
+            7
+        );
+    }
+
+    #[test]
+    fn test_no_chain() {
+        let (_, mappings) = super::parse_input(super::EXAMPLE_INPUT);
+        let error = super::find_chain(&mappings, "location", "seed").unwrap_err();
+        assert_eq!(
+            error,
+            crate::ChainError::NoPath {
+                from: "location".to_owned(),
+                to: "seed".to_owned(),
+            }
+        );
+        assert_eq!(error.to_string(), "no chain of maps from location to seed");
+        assert!(super::find_chain(&mappings, "seed", "gold").is_err());
+    }
+
+    #[test]
+    fn test_branching_chain() {
+        let (_, mappings) = super::parse_input(BRANCHING_INPUT);
+        let error = super::find_chain(&mappings, "seed", "location").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the chain of maps from seed to location branches at seed into soil, water"
+        );
+        // below the split there is only one way to go
+        let map = super::chain_map(&mappings, "soil", "location").unwrap();
+        assert_eq!(map.get(12), 102);
+    }
+
+    #[test]
+    fn test_many_chains() {
+        use std::fmt::Write;
+
+        // 2^40 chains from a0 to a40, each step going through either b or c
+        let mut input = String::from("seeds: 1");
+        for i in 0..40 {
+            for via in ["b", "c"] {
+                write!(input, "\n\na{i}-to-{via}{i} map:\n0 0 1").unwrap();
+                write!(input, "\n\n{via}{i}-to-a{} map:\n0 0 1", i + 1).unwrap();
+            }
+        }
+        let (_, mappings) = super::parse_input(&input);
+        let error = super::find_chain(&mappings, "a0", "a40").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the chain of maps from a0 to a40 branches at a39 into b39, c39"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate seed-to-soil map at line 6, first defined at line 3")]
+    fn test_duplicate_map() {
+        let _ =
+            super::parse_input("seeds: 1\n\nseed-to-soil map:\n0 0 1\n\nseed-to-soil map:\n5 0 1");
+    }
+}
+
+mod validation {
+    use crate::CategoryRange;
+
+    const OVERLAPPING_INPUT: &str = "seeds: 1 2 3
+
+seed-to-soil map:
+100 10 10
+200 15 10
+300 30 0
+100 40 5";
+
+    fn range(target: u64, source: u64, length: u64, line: usize) -> CategoryRange {
+        CategoryRange {
+            source,
+            target,
+            length,
+            line,
+        }
+    }
+
+    #[test]
+    fn test_inputs_are_valid() {
+        for input in [super::EXAMPLE_INPUT, super::INPUT] {
+            let (_, mappings) = super::parse_input(input);
+            for ranges in mappings.values() {
+                assert_eq!(super::find_issues(&ranges.0), vec![]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_reject_overlaps() {
+        use crate::{CategoryRanges, Precedence, RangeIssue};
+
+        let ranges = vec![
+            range(100, 10, 10, 4),
+            range(200, 15, 10, 5),
+            range(300, 30, 0, 6),
+            range(100, 40, 5, 7),
+            range(0, u64::MAX - 1, 3, 8),
+        ];
+        assert_eq!(
+            CategoryRanges::new(ranges, Precedence::Reject).unwrap_err(),
+            vec![
+                RangeIssue::Empty { line: 6 },
+                RangeIssue::Overflow { line: 8 },
+                RangeIssue::OverlappingSources { lines: (4, 5) },
+                RangeIssue::OverlappingTargets { lines: (4, 7) },
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid seed-to-soil map: line 6 has an empty range, \
+                    lines 4 and 5 have overlapping source ranges")]
+    fn test_parse_rejects_overlaps() {
+        let _ = super::parse_input(OVERLAPPING_INPUT);
+    }
+
+    #[test]
+    fn test_first_listed_wins() {
+        use crate::Precedence;
+
+        let (_, mappings) = super::parse_input_with(OVERLAPPING_INPUT, Precedence::FirstListed);
+        let ranges = mappings.values().next().unwrap();
+        assert_eq!(
+            ranges.0,
+            vec![
+                range(100, 10, 10, 4),
+                range(205, 20, 5, 5),
+                range(100, 40, 5, 7),
+            ]
+        );
+        assert_eq!(ranges.resolve_number(15), 105);
+        assert_eq!(ranges.resolve_number(20), 205);
+        assert_eq!(ranges.resolve_number(30), 30);
+        assert_eq!(ranges.inverse_number(100), vec![10, 40, 100]);
+    }
+
+    #[test]
+    fn test_overflow_is_always_rejected() {
+        use crate::{CategoryRanges, Precedence, RangeIssue};
+
+        let ranges = vec![range(u64::MAX, 0, 2, 4)];
+        assert_eq!(
+            CategoryRanges::new(ranges, Precedence::FirstListed).unwrap_err(),
+            vec![RangeIssue::Overflow { line: 4 }]
+        );
+    }
+}
+
+mod limits {
+    use std::fmt::Write;
+
+    const CATEGORIES: [&str; 8] = [
+        "seed",
+        "soil",
+        "fertilizer",
+        "water",
+        "light",
+        "temperature",
+        "humidity",
+        "location",
+    ];
+    // generated almanacs only map the top `WINDOW` numbers
+    const WINDOW: u64 = 4096;
+
+    const TOP_INPUT: &str = "seeds: 18446744073709551615 1 18446744073709551610 6
+
+seed-to-location map:
+0 18446744073709551610 6
+18446744073709551610 0 6";
+
+    // Every map cuts the top of the domain into chunks and lays them back out in another order,
+    // so some range reaches `u64::MAX` both as a source and as a target. `variant` picks the
+    // seeds, the chunk lengths and the order.
+    fn generate_almanac(variant: u64) -> String {
+        let low = u64::MAX - (WINDOW - 1);
+        let mut almanac = String::from("seeds:");
+        for i in 0..4 {
+            let start = low + (variant * 613 + i * 1031) % WINDOW;
+            let length = 1 + (variant * 7919 + i * 3037) % (u64::MAX - start + 1);
+            write!(almanac, " {start} {length}").unwrap();
+        }
+        almanac.push_str(" 18446744073709551615 1\n");
+
+        for (map, pair) in (0u64..).zip(CATEGORIES.windows(2)) {
+            write!(almanac, "\n{}-to-{} map:\n", pair[0], pair[1]).unwrap();
+            let mut chunks = vec![];
+            let mut start = low;
+            for i in 0u64.. {
+                let length = 1 + (variant * 211 + map * 89 + i * i * 37) % 512;
+                if length > u64::MAX - start {
+                    chunks.push((start, u64::MAX - start + 1));
+                    break;
+                }
+                chunks.push((start, length));
+                start += length;
+            }
+            let mut targets = chunks.clone();
+            if (variant + map).is_multiple_of(2) {
+                targets.reverse();
+            }
+            let shift = usize::try_from(variant * 5 + map * 3).unwrap() % targets.len();
+            targets.rotate_left(shift);
+            let mut target = low;
+            for (source, length) in targets {
+                writeln!(almanac, "{target} {source} {length}").unwrap();
+                target = target.wrapping_add(length);
+            }
+        }
+        almanac.trim_end().to_owned()
+    }
+
+    #[test]
+    fn test_ranges_reaching_the_top() {
+        use crate::DOMAIN_END;
+
+        let (seeds, mappings) = super::parse_input(TOP_INPUT);
+        let ranges = mappings.values().next().unwrap();
+        assert_eq!(ranges.resolve_number(u64::MAX), 5);
+        assert_eq!(ranges.resolve_number(5), u64::MAX);
+        assert_eq!(ranges.resolve_number(6), 6);
+        assert_eq!(ranges.inverse_number(u64::MAX), vec![5]);
+        let top = u128::from(u64::MAX);
+        assert_eq!(
+            ranges.resolve_numbers(top - 10..DOMAIN_END),
+            vec![top - 10..top - 5, 0..6]
+        );
+        assert_eq!(
+            ranges.resolve_numbers(0..8),
+            vec![top - 5..DOMAIN_END, 6..8]
+        );
+        assert_eq!(
+            ranges.inverse_numbers(top - 7..DOMAIN_END),
+            vec![0..6, top - 7..top - 5]
+        );
+        let chain = super::seed_to_location(&mappings);
+        assert_eq!(chain.get(top), 5);
+        assert_eq!(seeds, vec![u64::MAX, 1, u64::MAX - 5, 6]);
+    }
+
+    #[test]
+    fn test_generated_almanacs_near_the_top() {
+        use crate::range_set::RangeSet;
+        use crate::DOMAIN_END;
+
+        // start below the window so unmapped numbers are covered too
+        let low = u128::from(u64::MAX - (WINDOW - 1)) - 64;
+        let window: RangeSet<u128> = std::iter::once(low..DOMAIN_END).collect();
+        for variant in 1..=20 {
+            let almanac = generate_almanac(variant);
+            let (_, mappings) = super::parse_input(&almanac);
+            let chain = super::find_chain(&mappings, "seed", "location").unwrap();
+            let composed = super::seed_to_location(&mappings);
+
+            for category in &chain {
+                let mapping = &mappings[category];
+                let numbers = (low..DOMAIN_END).map(super::narrow);
+                let outputs: Vec<u64> =
+                    numbers.clone().map(|n| mapping.resolve_number(n)).collect();
+                // every map is a permutation of the window
+                let image: RangeSet<u128> = outputs
+                    .iter()
+                    .map(|&n| u128::from(n)..u128::from(n) + 1)
+                    .collect();
+                assert_eq!(image, window);
+                let resolved = mapping.resolve_numbers(low..DOMAIN_END);
+                assert_eq!(RangeSet::new(resolved.clone()), image);
+                for (n, output) in numbers.zip(&outputs) {
+                    assert_eq!(mapping.inverse_number(*output), vec![n]);
+                }
+                for range in resolved {
+                    let inverse = mapping.inverse_numbers(range.clone());
+                    let image: RangeSet<u128> = inverse
+                        .into_iter()
+                        .flat_map(|range| mapping.resolve_numbers(range))
+                        .collect();
+                    assert_eq!(image, std::iter::once(range).collect());
+                }
+            }
+
+            for n in (low..DOMAIN_END).step_by(7).chain([DOMAIN_END - 1]) {
+                let location = chain.iter().fold(super::narrow(n), |number, category| {
+                    mappings[category].resolve_number(number)
+                });
+                assert_eq!(composed.get(n), location.into());
+            }
+            let locations = composed.get_range(low..DOMAIN_END);
+            assert_eq!(RangeSet::new(locations), window);
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use std::ops::Range;
+use std::process::exit;
+use std::{env, fs};
+
+use day_5::trace::Almanac;
+
+// Traces seeds through an almanac:
+//   day-5 <almanac> [seed | start..end]...
+// Without seeds every seed listed in the almanac is traced.
+fn main() {
+    let mut args = env::args().skip(1);
+    let Some(path) = args.next() else {
+        eprintln!("usage: day-5 <almanac> [seed | start..end]...");
+        exit(2);
+    };
+    let input = fs::read_to_string(&path).unwrap_or_else(|e| {
+        eprintln!("could not read {path}: {e}");
+        exit(1);
+    });
+    let input = input.replace("\r\n", "\n");
+
+    let almanac = Almanac::parse(&input);
+    let mut seeds: Vec<Range<u128>> = args
+        .map(|arg| {
+            parse_seeds(&arg).unwrap_or_else(|e| {
+                eprintln!("invalid seed {arg}: {e}");
+                exit(2);
+            })
+        })
+        .collect();
+    if seeds.is_empty() {
+        seeds = almanac
+            .seeds()
+            .iter()
+            .map(|&seed| u128::from(seed)..u128::from(seed) + 1)
+            .collect();
+    }
+    for range in seeds {
+        println!("{}", almanac.trace(range));
+    }
+}
+
+// Seeds are u64, the end of a range may be one past `u64::MAX`.
+fn parse_seeds(arg: &str) -> Result<Range<u128>, String> {
+    let range = match arg.split_once("..") {
+        Some((start, end)) => start
+            .parse::<u64>()
+            .and_then(|start| Ok(u128::from(start)..end.parse()?)),
+        None => arg
+            .parse::<u64>()
+            .map(|seed| u128::from(seed)..u128::from(seed) + 1),
+    }
+    .map_err(|e| e.to_string())?;
+    if range.end > 1 << 64 {
+        return Err("range ends past u64::MAX + 1".to_owned());
+    }
+    if range.is_empty() {
+        return Err("range is empty".to_owned());
+    }
+    Ok(range)
+}
+
+#[test]
+fn test_parse_seeds() {
+    assert_eq!(parse_seeds("79"), Ok(79..80));
+    assert_eq!(parse_seeds("79..93"), Ok(79..93));
+    assert_eq!(parse_seeds("0..18446744073709551616"), Ok(0..1 << 64));
+    assert!(parse_seeds("0..18446744073709551617").is_err());
+    assert_eq!(parse_seeds("7..7"), Err("range is empty".to_owned()));
+    assert_eq!(parse_seeds("10..5"), Err("range is empty".to_owned()));
+    assert!(parse_seeds("seven").is_err());
+}
@@ -0,0 +1,145 @@
+use std::iter::Sum;
+use std::ops::{Range, Sub};
+
+// A set of numbers stored as sorted, non-overlapping and non-adjacent ranges.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RangeSet<T> {
+    ranges: Vec<Range<T>>,
+}
+
+impl<T> RangeSet<T>
+where
+    T: Copy + Ord + Sub<Output = T> + Sum,
+{
+    #[must_use]
+    pub fn new(ranges: Vec<Range<T>>) -> Self {
+        RangeSet {
+            ranges: normalize(ranges),
+        }
+    }
+
+    #[must_use]
+    pub fn ranges(&self) -> &[Range<T>] {
+        &self.ranges
+    }
+
+    // The number of ranges in the set, not the number of values.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    // The number of values in the set.
+    #[must_use]
+    pub fn total_length(&self) -> T {
+        self.ranges.iter().map(|r| r.end - r.start).sum()
+    }
+
+    #[must_use]
+    pub fn contains(&self, value: T) -> bool {
+        let index = self.ranges.partition_point(|r| r.end <= value);
+        self.ranges.get(index).is_some_and(|r| r.contains(&value))
+    }
+
+    #[must_use]
+    pub fn union(&self, other: &RangeSet<T>) -> RangeSet<T> {
+        let ranges = self.ranges.iter().chain(&other.ranges).cloned().collect();
+        RangeSet::new(ranges)
+    }
+
+    #[must_use]
+    pub fn intersection(&self, other: &RangeSet<T>) -> RangeSet<T> {
+        let mut ranges = vec![];
+        let (mut i, mut j) = (0, 0);
+        while let (Some(a), Some(b)) = (self.ranges.get(i), other.ranges.get(j)) {
+            let start = a.start.max(b.start);
+            let end = a.end.min(b.end);
+            if start < end {
+                ranges.push(start..end);
+            }
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        RangeSet { ranges }
+    }
+
+    #[must_use]
+    pub fn difference(&self, other: &RangeSet<T>) -> RangeSet<T> {
+        let mut ranges = vec![];
+        let mut j = 0;
+        for range in &self.ranges {
+            let mut start = range.start;
+            while start < range.end {
+                // skip ranges that end before the remainder of `range`
+                while other.ranges.get(j).is_some_and(|r| r.end <= start) {
+                    j += 1;
+                }
+                match other.ranges.get(j) {
+                    Some(r) if r.start < range.end => {
+                        if start < r.start {
+                            ranges.push(start..r.start);
+                        }
+                        start = r.end;
+                    }
+                    _ => {
+                        ranges.push(start..range.end);
+                        break;
+                    }
+                }
+            }
+        }
+        RangeSet { ranges }
+    }
+}
+
+impl<T> FromIterator<Range<T>> for RangeSet<T>
+where
+    T: Copy + Ord + Sub<Output = T> + Sum,
+{
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(iter: I) -> Self {
+        RangeSet::new(iter.into_iter().collect())
+    }
+}
+
+// Sorts the ranges and merges the ones that overlap or touch.
+fn normalize<T: Copy + Ord>(mut ranges: Vec<Range<T>>) -> Vec<Range<T>> {
+    ranges.retain(|r| !r.is_empty());
+    ranges.sort_unstable_by_key(|r| r.start);
+    let mut merged: Vec<Range<T>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if last.end >= range.start => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+#[test]
+fn test_normalize() {
+    let set = RangeSet::new(vec![10..20, 5..5, 0..3, 15..25, 25..30, 3..4]);
+    assert_eq!(set.ranges(), &[0..4, 10..30]);
+    assert_eq!(set.total_length(), 24);
+    assert!(set.contains(3) && set.contains(29));
+    assert!(!set.contains(4) && !set.contains(30));
+}
+
+#[test]
+fn test_set_operations() {
+    let a = RangeSet::new(vec![0..10, 20..30]);
+    let b = RangeSet::new(vec![5..25, 28..40]);
+    let union = a.union(&b);
+    assert_eq!((union.len(), union.total_length()), (1, 40));
+    assert_eq!(a.intersection(&b).ranges(), &[5..10, 20..25, 28..30]);
+    assert_eq!(a.difference(&b).ranges(), &[0..5, 25..28]);
+    assert_eq!(b.difference(&a).ranges(), &[10..20, 30..40]);
+    assert_eq!(a.difference(&a), RangeSet::default());
+}
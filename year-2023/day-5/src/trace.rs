@@ -0,0 +1,147 @@
+use std::fmt::{self, Display, Formatter};
+use std::ops::Range;
+
+use crate::CategoryRanges;
+
+// The numbers a category ends up with, each paired with the almanac line that mapped them
+// or `None` when they passed through unmapped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceStep {
+    pub category: String,
+    pub ranges: Vec<(Range<u128>, Option<usize>)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trace {
+    pub seeds: Range<u128>,
+    pub steps: Vec<TraceStep>,
+}
+
+// An almanac parsed once, to trace any number of seeds through it.
+#[derive(Debug)]
+pub struct Almanac<'a> {
+    seeds: Vec<u64>,
+    // the maps from seed to location in the order they are applied, named by their target
+    chain: Vec<(&'a str, CategoryRanges)>,
+}
+
+impl<'a> Almanac<'a> {
+    /// # Panics
+    ///
+    /// Panics if the almanac is invalid or has no single chain of maps from seed to location.
+    #[must_use]
+    pub fn parse(input: &'a str) -> Self {
+        let (seeds, mut mappings) = crate::parse_input(input);
+        let chain = crate::find_chain(&mappings, "seed", "location")
+            .unwrap()
+            .into_iter()
+            .map(|category| (category.target, mappings.remove(&category).unwrap()))
+            .collect();
+        Almanac { seeds, chain }
+    }
+
+    // The seeds listed at the top of the almanac.
+    #[must_use]
+    pub fn seeds(&self) -> &[u64] {
+        &self.seeds
+    }
+
+    // Follows `seeds` through every map from seed to location.
+    #[must_use]
+    pub fn trace(&self, seeds: Range<u128>) -> Trace {
+        let mut ranges = vec![seeds.clone()];
+        let mut steps = vec![];
+        for (category, mapping) in &self.chain {
+            let traced: Vec<(Range<u128>, Option<usize>)> = ranges
+                .into_iter()
+                .flat_map(|range| mapping.trace_numbers(range))
+                .collect();
+            ranges = traced.iter().map(|(range, _)| range.clone()).collect();
+            steps.push(TraceStep {
+                category: (*category).to_owned(),
+                ranges: traced,
+            });
+        }
+        Trace { seeds, steps }
+    }
+}
+
+fn write_range(f: &mut Formatter<'_>, range: &Range<u128>) -> fmt::Result {
+    if range.start + 1 == range.end {
+        write!(f, "{}", range.start)
+    } else {
+        write!(f, "{}..{}", range.start, range.end)
+    }
+}
+
+impl Display for Trace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "seed ")?;
+        write_range(f, &self.seeds)?;
+        writeln!(f)?;
+        for step in &self.steps {
+            write!(f, "  {} ", step.category)?;
+            for (i, (range, line)) in step.ranges.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write_range(f, range)?;
+                match line {
+                    Some(line) => write!(f, " (line {line})")?,
+                    None => write!(f, " (unmapped)")?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_trace_seed_example() {
+    let trace = Almanac::parse(crate::EXAMPLE_INPUT).trace(79..80);
+    assert_eq!(
+        trace.to_string(),
+        "seed 79
+  soil 81 (line 5)
+  fertilizer 81 (unmapped)
+  water 81 (unmapped)
+  light 74 (line 20)
+  temperature 78 (line 25)
+  humidity 78 (unmapped)
+  location 82 (line 32)
+"
+    );
+}
+
+#[test]
+fn test_trace_empty_range() {
+    let almanac = Almanac::parse(crate::EXAMPLE_INPUT);
+    let reversed = Range { start: 10, end: 5 };
+    for seeds in [7..7, reversed.clone()] {
+        let trace = almanac.trace(seeds);
+        assert_eq!(trace.steps.len(), 7);
+        assert!(trace.steps.iter().all(|step| step.ranges.is_empty()));
+    }
+    assert_eq!(
+        almanac.trace(reversed).to_string().lines().next(),
+        Some("seed 10..5")
+    );
+}
+
+#[test]
+fn test_trace_range_example() {
+    let trace = Almanac::parse(crate::EXAMPLE_INPUT).trace(79..93);
+    assert_eq!(trace.steps[0].ranges, vec![(81..95, Some(5))]);
+    let (_, mappings) = crate::parse_input(crate::EXAMPLE_INPUT);
+    let expected = crate::seed_to_location(&mappings).get_range(79..93);
+    let locations = trace.steps[6]
+        .ranges
+        .iter()
+        .map(|(range, _)| range.clone())
+        .collect();
+    assert_eq!(
+        crate::range_set::RangeSet::new(locations),
+        crate::range_set::RangeSet::new(expected)
+    );
+}
@@ -0,0 +1,379 @@
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::iter::Product;
+use std::ops::{Add, Mul, Shl, Shr, Sub};
+use std::str::FromStr;
+
+// An unsigned integer of any size, as little-endian 64 bit limbs without trailing zero limbs.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigUint {
+    limbs: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseBigUintError {
+    Empty,
+    InvalidDigit(char),
+}
+
+impl Display for ParseBigUintError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseBigUintError::Empty => write!(f, "cannot parse integer from empty string"),
+            ParseBigUintError::InvalidDigit(c) => write!(f, "invalid digit {c:?} in integer"),
+        }
+    }
+}
+
+impl Error for ParseBigUintError {}
+
+// 10^19, the largest power of ten that fits in a limb
+const DECIMAL_BASE: u64 = 10_000_000_000_000_000_000;
+
+impl BigUint {
+    fn from_limbs(mut limbs: Vec<u64>) -> Self {
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+        BigUint { limbs }
+    }
+
+    #[must_use]
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    #[must_use]
+    pub fn is_odd(&self) -> bool {
+        self.limbs.first().is_some_and(|limb| limb & 1 == 1)
+    }
+
+    // The number of bits needed to write the number down, 0 for zero.
+    #[must_use]
+    pub fn bits(&self) -> usize {
+        self.limbs.last().map_or(0, |last| {
+            self.limbs.len() * 64 - last.leading_zeros() as usize
+        })
+    }
+
+    #[must_use]
+    pub fn to_u64(&self) -> Option<u64> {
+        match self.limbs[..] {
+            [] => Some(0),
+            [limb] => Some(limb),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn checked_sub(&self, other: &BigUint) -> Option<BigUint> {
+        if *self < *other {
+            return None;
+        }
+        let mut limbs = Vec::with_capacity(self.limbs.len());
+        let mut borrow = false;
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let b = other.limbs.get(i).copied().unwrap_or(0);
+            let (difference, borrow_a) = a.overflowing_sub(b);
+            let (difference, borrow_b) = difference.overflowing_sub(u64::from(borrow));
+            limbs.push(difference);
+            borrow = borrow_a || borrow_b;
+        }
+        Some(BigUint::from_limbs(limbs))
+    }
+
+    // The largest integer whose square is not above `self`, computed bit by bit so it only
+    // needs shifts, additions and subtractions.
+    #[must_use]
+    pub fn isqrt(&self) -> BigUint {
+        if self.is_zero() {
+            return BigUint::default();
+        }
+        let mut remainder = self.clone();
+        let mut root = BigUint::default();
+        // the highest power of four not above `self`
+        let mut bit = &BigUint::from(1u64) << ((self.bits() - 1) & !1);
+        while !bit.is_zero() {
+            let candidate = &root + &bit;
+            root = &root >> 1;
+            if remainder >= candidate {
+                remainder = &remainder - &candidate;
+                root = &root + &bit;
+            }
+            bit = &bit >> 2;
+        }
+        root
+    }
+
+    /// The quotient and remainder of dividing by `divisor`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `divisor` is zero.
+    #[must_use]
+    pub fn div_rem(&self, divisor: &BigUint) -> (BigUint, BigUint) {
+        assert!(!divisor.is_zero(), "attempt to divide by zero");
+        // binary long division, one bit of the quotient at a time
+        let mut quotient = vec![0; self.limbs.len()];
+        let mut remainder = BigUint::default();
+        for i in (0..self.bits()).rev() {
+            remainder = &remainder << 1;
+            if (self.limbs[i / 64] >> (i % 64)) & 1 == 1 {
+                remainder = &remainder + &BigUint::from(1u64);
+            }
+            if remainder >= *divisor {
+                remainder = &remainder - divisor;
+                quotient[i / 64] |= 1 << (i % 64);
+            }
+        }
+        (BigUint::from_limbs(quotient), remainder)
+    }
+
+    // `self * factor + addend`
+    fn mul_add_small(&self, factor: u64, addend: u64) -> BigUint {
+        let mut limbs = Vec::with_capacity(self.limbs.len() + 1);
+        let mut carry = u128::from(addend);
+        for &limb in &self.limbs {
+            let (low, high) = split(u128::from(limb) * u128::from(factor) + carry);
+            limbs.push(low);
+            carry = u128::from(high);
+        }
+        limbs.push(split(carry).0);
+        BigUint::from_limbs(limbs)
+    }
+
+    // The quotient and remainder of dividing by `divisor`.
+    fn div_rem_small(&self, divisor: u64) -> (BigUint, u64) {
+        let mut limbs = vec![0; self.limbs.len()];
+        let mut remainder = 0u64;
+        for (i, &limb) in self.limbs.iter().enumerate().rev() {
+            let dividend = (u128::from(remainder) << 64) | u128::from(limb);
+            limbs[i] = split(dividend / u128::from(divisor)).0;
+            remainder = split(dividend % u128::from(divisor)).0;
+        }
+        (BigUint::from_limbs(limbs), remainder)
+    }
+}
+
+// The low and high limb of `value`.
+fn split(value: u128) -> (u64, u64) {
+    let low = u64::try_from(value & u128::from(u64::MAX)).unwrap();
+    let high = u64::try_from(value >> 64).unwrap();
+    (low, high)
+}
+
+impl From<u64> for BigUint {
+    fn from(value: u64) -> Self {
+        BigUint::from_limbs(vec![value])
+    }
+}
+
+impl From<u128> for BigUint {
+    fn from(value: u128) -> Self {
+        let (low, high) = split(value);
+        BigUint::from_limbs(vec![low, high])
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add for &BigUint {
+    type Output = BigUint;
+
+    fn add(self, other: &BigUint) -> BigUint {
+        let (long, short) = if self.limbs.len() >= other.limbs.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        let mut limbs = Vec::with_capacity(long.limbs.len() + 1);
+        let mut carry = false;
+        for (i, &a) in long.limbs.iter().enumerate() {
+            let b = short.limbs.get(i).copied().unwrap_or(0);
+            let (sum, carry_a) = a.overflowing_add(b);
+            let (sum, carry_b) = sum.overflowing_add(u64::from(carry));
+            limbs.push(sum);
+            carry = carry_a || carry_b;
+        }
+        limbs.push(u64::from(carry));
+        BigUint::from_limbs(limbs)
+    }
+}
+
+impl Sub for &BigUint {
+    type Output = BigUint;
+
+    fn sub(self, other: &BigUint) -> BigUint {
+        self.checked_sub(other)
+            .expect("attempt to subtract with overflow")
+    }
+}
+
+impl Mul for &BigUint {
+    type Output = BigUint;
+
+    fn mul(self, other: &BigUint) -> BigUint {
+        let mut limbs = vec![0; self.limbs.len() + other.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0;
+            for (j, &b) in other.limbs.iter().enumerate() {
+                let product = u128::from(a) * u128::from(b) + u128::from(limbs[i + j]) + carry;
+                let (low, high) = split(product);
+                limbs[i + j] = low;
+                carry = u128::from(high);
+            }
+            limbs[i + other.limbs.len()] = split(carry).0;
+        }
+        BigUint::from_limbs(limbs)
+    }
+}
+
+impl Shl<usize> for &BigUint {
+    type Output = BigUint;
+
+    fn shl(self, shift: usize) -> BigUint {
+        let (words, bits) = (shift / 64, shift % 64);
+        let mut limbs = vec![0; words];
+        let mut carry = 0;
+        for &limb in &self.limbs {
+            limbs.push((limb << bits) | carry);
+            carry = if bits == 0 { 0 } else { limb >> (64 - bits) };
+        }
+        limbs.push(carry);
+        BigUint::from_limbs(limbs)
+    }
+}
+
+impl Shr<usize> for &BigUint {
+    type Output = BigUint;
+
+    fn shr(self, shift: usize) -> BigUint {
+        let (words, bits) = (shift / 64, shift % 64);
+        let high = self.limbs.iter().skip(words + 1).map(Some).chain([None]);
+        let limbs = self
+            .limbs
+            .iter()
+            .skip(words)
+            .zip(high)
+            .map(|(&limb, next)| match next {
+                Some(next) if bits > 0 => (limb >> bits) | (next << (64 - bits)),
+                _ => limb >> bits,
+            })
+            .collect();
+        BigUint::from_limbs(limbs)
+    }
+}
+
+impl<'a> Product<&'a BigUint> for BigUint {
+    fn product<I: Iterator<Item = &'a BigUint>>(iter: I) -> Self {
+        iter.fold(BigUint::from(1u64), |product, value| &product * value)
+    }
+}
+
+impl Product for BigUint {
+    fn product<I: Iterator<Item = BigUint>>(iter: I) -> Self {
+        iter.fold(BigUint::from(1u64), |product, value| &product * &value)
+    }
+}
+
+impl FromStr for BigUint {
+    type Err = ParseBigUintError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(ParseBigUintError::Empty);
+        }
+        let mut value = BigUint::default();
+        for c in s.chars() {
+            let digit = c.to_digit(10).ok_or(ParseBigUintError::InvalidDigit(c))?;
+            value = value.mul_add_small(10, u64::from(digit));
+        }
+        Ok(value)
+    }
+}
+
+impl Display for BigUint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut chunks = vec![];
+        let mut value = self.clone();
+        while !value.is_zero() {
+            let (quotient, chunk) = value.div_rem_small(DECIMAL_BASE);
+            chunks.push(chunk);
+            value = quotient;
+        }
+        let Some((first, rest)) = chunks.split_last() else {
+            return write!(f, "0");
+        };
+        write!(f, "{first}")?;
+        for chunk in rest.iter().rev() {
+            write!(f, "{chunk:019}")?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_matches_u128() {
+    use crate::next;
+
+    let mut state = 0x9e37_79b9_7f4a_7c15;
+    for _ in 0..1000 {
+        let a = u128::from(next(&mut state)) << (next(&mut state) % 64);
+        let b = u128::from(next(&mut state) >> (next(&mut state) % 64));
+        let (x, y) = (BigUint::from(a), BigUint::from(b));
+        assert_eq!(&x + &y, BigUint::from(a + b));
+        assert_eq!(x.checked_sub(&y), a.checked_sub(b).map(BigUint::from));
+        assert_eq!(&BigUint::from(a >> 64) * &y, BigUint::from((a >> 64) * b));
+        assert_eq!(&x >> 3, BigUint::from(a >> 3));
+        assert_eq!(&(&x >> 64) << 64, BigUint::from(a & !u128::from(u64::MAX)));
+        assert_eq!(x.isqrt(), BigUint::from(a.isqrt()));
+        if let (Some(quotient), Some(remainder)) = (a.checked_div(b), a.checked_rem(b)) {
+            let expected = (BigUint::from(quotient), BigUint::from(remainder));
+            assert_eq!(x.div_rem(&y), expected);
+        }
+        assert_eq!(x.cmp(&y), a.cmp(&b));
+        assert_eq!(x.to_string(), a.to_string());
+        assert_eq!(a.to_string().parse(), Ok(x));
+    }
+}
+
+#[test]
+fn test_large_numbers() {
+    let googol: BigUint = format!("1{}", "0".repeat(100)).parse().unwrap();
+    let ten_to_fifty: BigUint = format!("1{}", "0".repeat(50)).parse().unwrap();
+    assert_eq!(&ten_to_fifty * &ten_to_fifty, googol);
+    assert_eq!(googol.isqrt(), ten_to_fifty);
+    let one = BigUint::from(1u64);
+    assert_eq!((&googol - &one).isqrt(), &ten_to_fifty - &one);
+    assert_eq!((&googol + &one).isqrt(), ten_to_fifty);
+    assert_eq!(googol.bits(), 333);
+    assert_eq!(
+        googol.div_rem(&ten_to_fifty),
+        (ten_to_fifty.clone(), BigUint::default())
+    );
+    assert_eq!(
+        (&googol - &one).to_string(),
+        "9".repeat(100),
+        "carries across limbs"
+    );
+    assert_eq!(googol.to_u64(), None);
+    assert_eq!("".parse::<BigUint>(), Err(ParseBigUintError::Empty));
+    assert_eq!(
+        "12x".parse::<BigUint>(),
+        Err(ParseBigUintError::InvalidDigit('x'))
+    );
+}
@@ -1,53 +1,285 @@
+use std::ops::RangeInclusive;
+
+use crate::big_uint::BigUint;
+
+pub mod big_uint;
+
 const INPUT: &str = include_str!("../puzzle_input");
 const EXAMPLE_INPUT: &str = "Time:      7  15   30
 Distance:  9  40  200";
 
-#[derive(Debug, Copy, Clone)]
-struct Race {
-    time: u64,
-    distance: u64,
+// How the boat charges and slows down. The puzzle's boat gains 1 mm/ms of speed for every
+// ms the button is held, can be held for the whole race and has no drag.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Boat {
+    // speed gained for every ms the button is held
+    pub acceleration: u64,
+    // the button cannot be held for longer than this
+    pub max_charge: Option<u64>,
+    // mm lost for every ms the boat moves, a boat slower than that does not move at all
+    pub drag: u64,
+}
+
+impl Default for Boat {
+    fn default() -> Self {
+        Boat {
+            acceleration: 1,
+            max_charge: None,
+            drag: 0,
+        }
+    }
+}
+
+// A race lasting `time` ms, holding the button for `h` ms moves the boat
+// `(time - h) * (acceleration * h - drag)` mm.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Race {
+    pub time: u64,
+    // the record to beat
+    pub distance: u64,
+    pub boat: Boat,
 }
 
 impl Race {
-    fn winning_inputs(self) -> u64 {
-        let range = 0..=self.time;
-        range
+    #[must_use]
+    pub fn new(time: u64, distance: u64) -> Self {
+        Race {
+            time,
+            distance,
+            boat: Boat::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_boat(self, boat: Boat) -> Self {
+        Race { boat, ..self }
+    }
+
+    // The longest the button can be held.
+    #[must_use]
+    pub fn max_hold(self) -> u64 {
+        self.boat
+            .max_charge
+            .map_or(self.time, |charge| charge.min(self.time))
+    }
+
+    #[must_use]
+    pub fn winning_inputs(self) -> u64 {
+        self.winning_holds()
+            .map_or(0, |holds| holds.end() - holds.start() + 1)
+    }
+
+    #[must_use]
+    pub fn winning_holds(self) -> Option<RangeInclusive<u64>> {
+        if self.boat == Boat::default() {
+            self.puzzle_holds()
+        } else {
+            self.boat_holds()
+        }
+    }
+
+    // Holding for `m` ms wins when `m * (time - m) > distance`, which is the same as
+    // `(2m - time)^2 < time^2 - 4 * distance`. So the winning holds are the ones where
+    // `k = 2m - time` has the parity of `time` and a square below that discriminant.
+    fn puzzle_holds(self) -> Option<RangeInclusive<u64>> {
+        let time = u128::from(self.time);
+        let discriminant = (time * time).saturating_sub(4 * u128::from(self.distance));
+        if discriminant == 0 {
+            return None;
+        }
+        // the largest k with k^2 < discriminant, every k in -k..=k with the right parity wins
+        let k = (discriminant - 1).isqrt();
+        let k = if k % 2 == time % 2 {
+            k
+        } else {
+            k.checked_sub(1)?
+        };
+        // k^2 < time^2, so it always fits
+        let k = u64::try_from(k).ok()?;
+        let min = (self.time - k) / 2;
+        Some(min..=self.time - min)
+    }
+
+    // With `a` the acceleration, holding for `h` ms wins when
+    // `(2ah - b)^2 < b^2 - 4a * (drag * time + distance)` with `b = a * time + drag`.
+    // The squares need more than 128 bits, so this is solved in big integers.
+    fn boat_holds(self) -> Option<RangeInclusive<u64>> {
+        if self.boat.acceleration == 0 {
+            return None;
+        }
+        let acceleration = BigUint::from(self.boat.acceleration);
+        let time = BigUint::from(self.time);
+        let drag = BigUint::from(self.boat.drag);
+        let b = &(&acceleration * &time) + &drag;
+        let c = &(&drag * &time) + &BigUint::from(self.distance);
+        let discriminant = (&b * &b).checked_sub(&(&(&acceleration * &c) << 2))?;
+        if discriminant.is_zero() {
+            return None;
+        }
+        // the largest k with k^2 < discriminant, so `b - k <= 2ah <= b + k`, and k < b
+        let k = (&discriminant - &BigUint::from(1u64)).isqrt();
+        let divisor = &acceleration << 1;
+        let (min, remainder) = (&b - &k).div_rem(&divisor);
+        let min = if remainder.is_zero() {
+            min
+        } else {
+            &min + &BigUint::from(1u64)
+        };
+        let (max, _) = (&b + &k).div_rem(&divisor);
+
+        // past the end of the race the quadratic has winners the boat cannot use
+        let max_hold = BigUint::from(self.max_hold());
+        let max = max.min(max_hold).to_u64()?;
+        let min = min.to_u64()?;
+        (min <= max).then_some(min..=max)
+    }
+
+    /// How far the boat gets when holding the button for `hold` ms, which can take up to
+    /// 192 bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the button cannot be held for `hold` ms.
+    #[must_use]
+    pub fn travelled(self, hold: u64) -> BigUint {
+        assert!(
+            hold <= self.max_hold(),
+            "holding for {hold} ms when at most {} ms is possible",
+            self.max_hold()
+        );
+        // the product of two u64 always fits in a u128
+        let speed = u128::from(self.boat.acceleration) * u128::from(hold);
+        let speed = speed.saturating_sub(u128::from(self.boat.drag));
+        &BigUint::from(speed) * &BigUint::from(self.time - hold)
+    }
+
+    // The distance peaks at `(a * time + drag) / 2a` and falls off on both sides, so the best
+    // hold is the closest one on either side of it. Ties go to the shorter hold.
+    #[must_use]
+    pub fn optimal_hold(self) -> u64 {
+        if self.boat.acceleration == 0 {
+            return 0;
+        }
+        let acceleration = u128::from(self.boat.acceleration);
+        let peak = (acceleration * u128::from(self.time) + u128::from(self.boat.drag))
+            / (2 * acceleration);
+        let max_hold = self.max_hold();
+        let below = u64::try_from(peak).map_or(max_hold, |peak| peak.min(max_hold));
+        let above = (below + 1).min(max_hold);
+        let (below_distance, above_distance) = (self.travelled(below), self.travelled(above));
+        if above_distance > below_distance {
+            above
+        } else if !below_distance.is_zero() {
+            below
+        } else {
+            // too much drag to move at all, so every hold is as good as none
+            0
+        }
+    }
+
+    #[must_use]
+    pub fn best_distance(self) -> BigUint {
+        self.travelled(self.optimal_hold())
+    }
+
+    // By how much holding for `hold` ms beats the record, `None` when it does not.
+    #[must_use]
+    pub fn margin(self, hold: u64) -> Option<BigUint> {
+        let margin = self
+            .travelled(hold)
+            .checked_sub(&BigUint::from(self.distance))?;
+        (!margin.is_zero()).then_some(margin)
+    }
+
+    // Every winning hold time paired with its margin.
+    pub fn margins(self) -> impl Iterator<Item = (u64, BigUint)> {
+        let record = BigUint::from(self.distance);
+        self.winning_holds()
             .into_iter()
-            .filter(|m| (self.time - m) * m > self.distance)
-            .count() as u64
+            .flatten()
+            .map(move |hold| (hold, &self.travelled(hold) - &record))
+    }
+}
+
+// A race with numbers of any size, solved as a `Race` whenever they fit in a u64.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BigRace {
+    pub time: BigUint,
+    pub distance: BigUint,
+}
+
+impl BigRace {
+    #[must_use]
+    pub fn to_race(&self) -> Option<Race> {
+        Some(Race::new(self.time.to_u64()?, self.distance.to_u64()?))
+    }
+
+    #[must_use]
+    pub fn winning_inputs(&self) -> BigUint {
+        match self.to_race() {
+            Some(race) => race.winning_inputs().into(),
+            None => self.winning_inputs_big(),
+        }
+    }
+
+    // The same count as `Race::winning_inputs`, in big integers.
+    fn winning_inputs_big(&self) -> BigUint {
+        let square = &self.time * &self.time;
+        let Some(discriminant) = square.checked_sub(&(&self.distance << 2)) else {
+            return BigUint::default();
+        };
+        if discriminant.is_zero() {
+            return BigUint::default();
+        }
+        let one = BigUint::from(1u64);
+        let k = (&discriminant - &one).isqrt();
+        if k.is_odd() == self.time.is_odd() {
+            &k + &one
+        } else {
+            k
+        }
     }
 }
 
+// xorshift64 stream for the races and boats the tests generate, seeded per test
+fn next(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
 mod part_1 {
-    use crate::Race;
+    use crate::big_uint::BigUint;
+    use crate::BigRace;
 
-    fn solution(input: &str) -> u64 {
+    fn solution(input: &str) -> BigUint {
         let races = parse_input(input);
-        races.into_iter().map(Race::winning_inputs).product()
+        races.iter().map(BigRace::winning_inputs).product()
     }
 
-    fn parse_input(input: &str) -> Vec<Race> {
+    fn parse_input(input: &str) -> Vec<BigRace> {
         let mut lines = input.lines();
         let time_line = lines.next().unwrap();
         let distance_line = lines.next().unwrap();
         let times = time_line
             .split_whitespace()
             .skip(1)
-            .map(|s| s.parse::<u64>().unwrap());
+            .map(|s| s.parse::<BigUint>().unwrap());
         let distances = distance_line
             .split_whitespace()
             .skip(1)
-            .map(|s| s.parse::<u64>().unwrap());
+            .map(|s| s.parse::<BigUint>().unwrap());
         times
             .zip(distances)
-            .map(|(time, distance)| Race { time, distance })
+            .map(|(time, distance)| BigRace { time, distance })
             .collect()
     }
 
     #[test]
     fn test_parse_example_input() {
         let input = super::EXAMPLE_INPUT;
-        assert_eq!(solution(input), 288);
+        assert_eq!(solution(input), BigUint::from(288u64));
     }
 
     #[test]
@@ -57,14 +289,15 @@ mod part_1 {
 }
 
 mod part_2 {
-    use crate::Race;
+    use crate::big_uint::BigUint;
+    use crate::BigRace;
 
-    fn solution(input: &str) -> u64 {
+    fn solution(input: &str) -> BigUint {
         let race = parse_input(input);
         race.winning_inputs()
     }
 
-    fn parse_input(input: &str) -> Race {
+    fn parse_input(input: &str) -> BigRace {
         let mut lines = input.lines();
         let time_line = lines.next().unwrap();
         let distance_line = lines.next().unwrap();
@@ -72,7 +305,7 @@ mod part_2 {
         let time_text = time_text.trim().replace(' ', "");
         let (_, distance_text) = distance_line.split_once(' ').unwrap();
         let distance_text = distance_text.trim().replace(' ', "");
-        Race {
+        BigRace {
             time: time_text.parse().unwrap(),
             distance: distance_text.parse().unwrap(),
         }
@@ -81,7 +314,17 @@ mod part_2 {
     #[test]
     fn test_parse_example_input() {
         let input = super::EXAMPLE_INPUT;
-        assert_eq!(solution(input), 71503);
+        assert_eq!(solution(input), BigUint::from(71503u64));
+    }
+
+    #[test]
+    fn test_long_input() {
+        // the concatenated numbers no longer fit in a u64
+        let input = "Time:      71530 71530 71530 71530
+Distance:  940200 940200 940200 940200";
+        let race = parse_input(input);
+        assert_eq!(race.to_race(), None);
+        assert_eq!(race.winning_inputs().to_string(), "71530715307153045241");
     }
 
     #[test]
@@ -89,3 +332,275 @@ mod part_2 {
         eprintln!("Solution Day 6 Part 2: [{}]", solution(super::INPUT));
     }
 }
+
+// Counts the winning holds by trying each of them, which is slow but leaves no room for the
+// off-by-one and parity mistakes the square root in `puzzle_holds` could make.
+mod brute_force {
+    use crate::Race;
+
+    fn winning_inputs(race: Race) -> u64 {
+        let range = 0..=race.time;
+        range
+            .into_iter()
+            .filter(|m| (race.time - m) * m > race.distance)
+            .count() as u64
+    }
+
+    #[test]
+    fn test_small_races() {
+        for time in 0..=64 {
+            // past time^2 / 4 no hold time wins
+            for distance in 0..=time * time / 4 + 2 {
+                let race = Race::new(time, distance);
+                assert_eq!(race.winning_inputs(), winning_inputs(race), "{race:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_generated_races() {
+        use crate::next;
+
+        let mut state = 0x2545_f491_4f6c_dd1d;
+        for _ in 0..2000 {
+            let time = next(&mut state) % 5000;
+            let best = (time / 2) * (time - time / 2);
+            // distances close to a record where the roots are perfect squares are the tricky ones
+            let distance = match next(&mut state) % 3 {
+                0 => next(&mut state) % (best + 1),
+                1 => {
+                    let k = next(&mut state) % (time / 2 + 1);
+                    best - k * k - (time % 2) * k
+                }
+                _ => best.saturating_sub(next(&mut state) % 4),
+            };
+            let race = Race::new(time, distance);
+            assert_eq!(race.winning_inputs(), winning_inputs(race), "{race:?}");
+            let holds: Vec<u64> = (0..=time).filter(|&m| (time - m) * m > distance).collect();
+            let expected = holds.first().map(|&min| min..=*holds.last().unwrap());
+            assert_eq!(race.winning_holds(), expected, "{race:?}");
+        }
+    }
+
+    #[test]
+    fn test_large_races() {
+        let race = Race::new(u64::MAX, 0);
+        assert_eq!(race.winning_inputs(), u64::MAX - 1);
+        let time = u64::from(u32::MAX) * 2;
+        let best = (time / 2) * (time / 2);
+        for (distance, expected) in [(best, 0), (best - 1, 1), (best - 2, 3), (best - 4, 3)] {
+            assert_eq!(Race::new(time, distance).winning_inputs(), expected);
+        }
+    }
+}
+
+mod analysis {
+    #[test]
+    fn test_example_race() {
+        let race = crate::Race::new(7, 9);
+        assert_eq!(race.winning_holds(), Some(2..=5));
+        assert_eq!(race.optimal_hold(), 3);
+        assert_eq!(race.best_distance(), 12u64.into());
+        assert!(race.travelled(7).is_zero());
+        assert_eq!(race.margin(1), None);
+        assert_eq!(race.margin(4), Some(3u64.into()));
+        let margins: Vec<(u64, u64)> = race
+            .margins()
+            .map(|(hold, margin)| (hold, margin.to_u64().unwrap()))
+            .collect();
+        assert_eq!(margins, vec![(2, 1), (3, 3), (4, 3), (5, 1)]);
+    }
+
+    #[test]
+    fn test_unwinnable_race() {
+        let race = crate::Race::new(30, 225);
+        assert_eq!(race.best_distance(), 225u64.into());
+        assert_eq!(race.winning_holds(), None);
+        assert_eq!(race.margins().count(), 0);
+        assert_eq!(race.winning_inputs(), 0);
+    }
+
+    #[test]
+    fn test_margins_match_holds() {
+        for (time, distance) in [(15, 40), (30, 200), (71530, 940_200)] {
+            let race = crate::Race::new(time, distance);
+            let holds = race.winning_holds().unwrap();
+            assert!(race.margin(holds.start() - 1).is_none());
+            assert!(race.margin(holds.end() + 1).is_none());
+            assert!(holds.contains(&race.optimal_hold()));
+            let best = race.margins().map(|(_, margin)| margin).max().unwrap();
+            assert_eq!(best, &race.best_distance() - &distance.into());
+        }
+    }
+}
+
+mod big_race {
+    use crate::{BigRace, Race};
+
+    fn big(race: Race) -> BigRace {
+        BigRace {
+            time: race.time.into(),
+            distance: race.distance.into(),
+        }
+    }
+
+    #[test]
+    fn test_big_path_matches_race() {
+        use crate::big_uint::BigUint;
+        use crate::next;
+
+        let mut state = 0x5851_f42d_4c95_7f2d;
+        for _ in 0..500 {
+            let time = next(&mut state) >> (next(&mut state) % 64);
+            let best = u128::from(time / 2) * u128::from(time - time / 2);
+            let distance = u64::try_from(best.min(u128::from(next(&mut state)))).unwrap();
+            let race = Race::new(time, distance);
+            let expected = BigUint::from(race.winning_inputs());
+            assert_eq!(big(race).winning_inputs_big(), expected, "{race:?}");
+            assert_eq!(big(race).winning_inputs(), expected, "{race:?}");
+        }
+    }
+
+    #[test]
+    fn test_races_past_u64() {
+        use crate::big_uint::BigUint;
+
+        let half: BigUint = format!("1{}", "0".repeat(30)).parse().unwrap();
+        let time = &half << 1;
+        let square = &half * &half;
+        for j in 1..20u64 {
+            let j = BigUint::from(j);
+            // the record is set by holding `j` ms off the optimum, so only holds closer to it win
+            let even = BigRace {
+                time: time.clone(),
+                distance: &square - &(&j * &j),
+            };
+            assert_eq!(even.winning_inputs(), &(&j << 1) - &BigUint::from(1u64));
+            let odd = BigRace {
+                time: &time + &BigUint::from(1u64),
+                distance: &(&square + &half) - &(&j * &(&j + &BigUint::from(1u64))),
+            };
+            assert_eq!(odd.winning_inputs(), &j << 1);
+        }
+        let unwinnable = BigRace {
+            time,
+            distance: square,
+        };
+        assert_eq!(unwinnable.winning_inputs(), BigUint::default());
+    }
+}
+
+#[cfg(test)]
+mod physics {
+    use crate::{next, Boat, Race};
+
+    fn generate_boat(state: &mut u64, time: u64) -> Boat {
+        Boat {
+            acceleration: next(state) % 6,
+            max_charge: next(state)
+                .is_multiple_of(3)
+                .then(|| next(state) % (time + 3)),
+            drag: next(state) % 40,
+        }
+    }
+
+    #[test]
+    fn test_generated_boats() {
+        let mut state = 0x1405_7b7e_f767_814f;
+        for _ in 0..3000 {
+            let time = next(&mut state) % 80;
+            let boat = generate_boat(&mut state, time);
+            let race = Race::new(time, 0).with_boat(boat);
+            let travelled: Vec<u64> = (0..=race.max_hold())
+                .map(|h| race.travelled(h).to_u64().unwrap())
+                .collect();
+            let best = *travelled.iter().max().unwrap();
+            let optimal = travelled.iter().position(|&d| d == best).unwrap();
+            assert_eq!(race.optimal_hold(), optimal as u64, "{race:?}");
+            assert_eq!(race.best_distance(), best.into(), "{race:?}");
+
+            let record = next(&mut state) % (best + 3);
+            let race = Race::new(time, record).with_boat(boat);
+            let holds: Vec<u64> = (0..=race.max_hold())
+                .filter(|&h| race.travelled(h) > record.into())
+                .collect();
+            let expected = holds.first().map(|&min| min..=*holds.last().unwrap());
+            assert_eq!(race.winning_holds(), expected, "{race:?}");
+            assert_eq!(race.winning_inputs(), holds.len() as u64, "{race:?}");
+        }
+    }
+
+    #[test]
+    fn test_puzzle_boat_in_big_integers() {
+        let mut state = 0x2545_f491_4f6c_dd1d;
+        for _ in 0..500 {
+            let time = next(&mut state) >> (next(&mut state) % 64);
+            let best = u128::from(time / 2) * u128::from(time - time / 2);
+            let distance = u64::try_from(best.min(u128::from(next(&mut state)))).unwrap();
+            let race = Race::new(time, distance);
+            assert_eq!(race.boat_holds(), race.puzzle_holds(), "{race:?}");
+        }
+    }
+
+    #[test]
+    fn test_large_boats() {
+        let boat = Boat {
+            acceleration: 1 << 40,
+            max_charge: Some(3 << 38),
+            drag: 1 << 50,
+        };
+        let race = Race::new(1 << 40, u64::MAX).with_boat(boat);
+        let holds = race.winning_holds().unwrap();
+        assert_eq!(
+            *holds.end(),
+            3 << 38,
+            "the charge limit cuts off the winning holds"
+        );
+        assert!(race.margin(*holds.start()).is_some());
+        assert!(race.margin(holds.start() - 1).is_none());
+        assert!(holds.contains(&race.optimal_hold()));
+
+        let still = Boat {
+            acceleration: 0,
+            ..boat
+        };
+        assert_eq!(race.with_boat(still).winning_holds(), None);
+        assert!(race.with_boat(still).best_distance().is_zero());
+    }
+
+    #[test]
+    fn test_distances_past_u128() {
+        use crate::big_uint::BigUint;
+
+        let boat = Boat {
+            acceleration: 1 << 63,
+            ..Boat::default()
+        };
+        let race = Race::new(1 << 63, 0).with_boat(boat);
+        assert_eq!(race.optimal_hold(), 1 << 62);
+        assert_eq!(race.best_distance(), &BigUint::from(1u64) << 187);
+        assert_eq!(race.winning_holds(), Some(1..=(1 << 63) - 1));
+
+        let boat = Boat {
+            acceleration: u64::MAX,
+            max_charge: None,
+            drag: u64::MAX,
+        };
+        let race = Race::new(u64::MAX, u64::MAX).with_boat(boat);
+        let holds = race.winning_holds().unwrap();
+        assert!(holds.contains(&race.optimal_hold()));
+        assert!(race.margin(*holds.start()).is_some());
+        assert!(race.margin(holds.start() - 1).is_none());
+        assert!(race.margin(*holds.end()).is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "holding for 5 ms when at most 4 ms is possible")]
+    fn test_hold_past_max_charge() {
+        let boat = Boat {
+            max_charge: Some(4),
+            ..Boat::default()
+        };
+        let _ = Race::new(10, 0).with_boat(boat).travelled(5);
+    }
+}
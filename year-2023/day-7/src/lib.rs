@@ -1,3 +1,7 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter, Write};
+
 const INPUT: &str = include_str!("../puzzle_input");
 const EXAMPLE_INPUT: &str = "32T3K 765
 T55J5 684
@@ -5,162 +9,592 @@ KK677 28
 KTJJT 220
 QQQJA 483";
 
-mod part_1 {
-    use std::cmp::Ordering;
-    use std::collections::BTreeMap;
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum Card {
+    Two,
+    Three,
+    Four,
+    Five,
+    Six,
+    Seven,
+    Eight,
+    Nine,
+    Ten,
+    Jack,
+    Queen,
+    King,
+    Ace,
+}
 
-    fn solution(input: &str) -> u64 {
-        let mut hands = parse_input(input);
-        hands.sort();
-        hands
-            .iter()
-            .enumerate()
-            .map(|(rank, hand)| hand.bid * (rank as u64 + 1))
-            .sum()
+impl Card {
+    pub const ALL: [Card; 13] = [
+        Card::Two,
+        Card::Three,
+        Card::Four,
+        Card::Five,
+        Card::Six,
+        Card::Seven,
+        Card::Eight,
+        Card::Nine,
+        Card::Ten,
+        Card::Jack,
+        Card::Queen,
+        Card::King,
+        Card::Ace,
+    ];
+}
+
+impl From<char> for Card {
+    fn from(c: char) -> Self {
+        match c {
+            '2' => Card::Two,
+            '3' => Card::Three,
+            '4' => Card::Four,
+            '5' => Card::Five,
+            '6' => Card::Six,
+            '7' => Card::Seven,
+            '8' => Card::Eight,
+            '9' => Card::Nine,
+            'T' => Card::Ten,
+            'J' => Card::Jack,
+            'Q' => Card::Queen,
+            'K' => Card::King,
+            'A' => Card::Ace,
+            _ => panic!("invalid card: {c}"),
+        }
     }
+}
 
-    fn parse_input(input: &str) -> Vec<Hand> {
-        input.lines().map(Hand::from_line).collect()
+impl Display for Card {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let label = "23456789TJQKA".as_bytes()[*self as usize];
+        write!(f, "{}", label as char)
     }
+}
 
-    #[test]
-    fn test_parse_example_input() {
-        let input = super::EXAMPLE_INPUT;
-        assert_eq!(solution(input), 6440);
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum HandType {
+    HighCard,
+    OnePair,
+    TwoPair,
+    ThreeOfAKind,
+    FullHouse,
+    FourOfAKind,
+    FiveOfAKind,
+}
+
+impl HandType {
+    // Wild cards join the biggest group of the other cards.
+    #[must_use]
+    pub fn classify(cards: &[Card; 5], rules: &Rules) -> Self {
+        let mut tree_map = BTreeMap::<Card, u8>::new();
+        for &card in cards {
+            *tree_map.entry(card).or_default() += 1;
+        }
+        let mut hand_type = HandType::HighCard;
+        let mut wilds: u8 = rules
+            .wild
+            .iter()
+            .filter_map(|card| tree_map.get(card))
+            .sum();
+        let mut card_sets: Vec<_> = tree_map.into_iter().collect();
+        card_sets.sort_by_key(|&(_, count)| count);
+        card_sets.reverse();
+
+        for (card, count) in card_sets {
+            if rules.is_wild(card) {
+                continue;
+            }
+            hand_type = match (count + wilds, hand_type) {
+                (2, HandType::HighCard) => HandType::OnePair,
+                (2, HandType::OnePair) => HandType::TwoPair,
+                (2, HandType::ThreeOfAKind) | (3, HandType::OnePair) => HandType::FullHouse,
+                (3, HandType::HighCard) => HandType::ThreeOfAKind,
+                (4, _) => HandType::FourOfAKind,
+                (5, _) => HandType::FiveOfAKind,
+                _ => hand_type,
+            };
+            wilds = 0;
+        }
+        if wilds == 5 {
+            hand_type = HandType::FiveOfAKind;
+        }
+        hand_type
     }
+}
 
-    #[test]
-    fn test_parse_input() {
-        eprintln!("Solution Day 7 Part 1: [{}]", solution(super::INPUT));
+impl Display for HandType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            HandType::HighCard => "high card",
+            HandType::OnePair => "one pair",
+            HandType::TwoPair => "two pair",
+            HandType::ThreeOfAKind => "three of a kind",
+            HandType::FullHouse => "full house",
+            HandType::FourOfAKind => "four of a kind",
+            HandType::FiveOfAKind => "five of a kind",
+        })
     }
+}
+
+// Hand types by the sizes of their groups of equal cards, biggest group first.
+const SIGNATURES: [(&[u8], HandType); 7] = [
+    (&[5], HandType::FiveOfAKind),
+    (&[4, 1], HandType::FourOfAKind),
+    (&[3, 2], HandType::FullHouse),
+    (&[3, 1, 1], HandType::ThreeOfAKind),
+    (&[2, 2, 1], HandType::TwoPair),
+    (&[2, 1, 1, 1], HandType::OnePair),
+    (&[1, 1, 1, 1, 1], HandType::HighCard),
+];
 
-    #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
-    enum Card {
-        Two,
-        Three,
-        Four,
-        Five,
-        Six,
-        Seven,
-        Eight,
-        Nine,
-        Ten,
-        Jack,
-        Queen,
-        King,
-        Ace,
-    }
-
-    impl From<char> for Card {
-        fn from(c: char) -> Self {
-            match c {
-                '2' => Card::Two,
-                '3' => Card::Three,
-                '4' => Card::Four,
-                '5' => Card::Five,
-                '6' => Card::Six,
-                '7' => Card::Seven,
-                '8' => Card::Eight,
-                '9' => Card::Nine,
-                'T' => Card::Ten,
-                'J' => Card::Jack,
-                'Q' => Card::Queen,
-                'K' => Card::King,
-                'A' => Card::Ace,
-                _ => panic!("invalid card: {c}"),
+impl HandType {
+    /// Looks up the type by the sorted group sizes of the cards, wild cards join the biggest
+    /// group since that beats every other use of them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the group sizes do not add up to five cards, which cannot happen.
+    #[must_use]
+    pub fn from_signature(cards: &[Card; 5], rules: &Rules) -> Self {
+        let mut counts = [0u8; 13];
+        let mut wilds = 0;
+        for &card in cards {
+            if rules.is_wild(card) {
+                wilds += 1;
+            } else {
+                counts[card as usize] += 1;
             }
         }
+        let mut signature: Vec<u8> = counts.into_iter().filter(|&count| count > 0).collect();
+        signature.sort_unstable_by(|a, b| b.cmp(a));
+        match signature.first_mut() {
+            Some(biggest) => *biggest += wilds,
+            None => signature.push(wilds),
+        }
+        SIGNATURES
+            .iter()
+            .find(|(known, _)| *known == signature.as_slice())
+            .map(|&(_, hand_type)| hand_type)
+            .unwrap()
     }
+}
 
-    #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
-    enum HandType {
-        HighCard,
-        OnePair,
-        TwoPair,
-        ThreeOfAKind,
-        FullHouse,
-        FourOfAKind,
-        FiveOfAKind,
+// How hands of the same type are ordered.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TieBreak {
+    // card by card in the order they were dealt
+    Dealt,
+    // biggest group first and stronger cards first within groups of the same size, like poker
+    Grouped,
+}
+
+// A Camel Cards variant.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Rules {
+    // weakest card first
+    pub order: [Card; 13],
+    pub wild: Vec<Card>,
+    pub tie_break: TieBreak,
+}
+
+impl Rules {
+    // Part 1, `J` is a jack.
+    #[must_use]
+    pub fn standard() -> Self {
+        Rules {
+            order: Card::ALL,
+            wild: vec![],
+            tie_break: TieBreak::Dealt,
+        }
     }
 
-    impl From<&[Card; 5]> for HandType {
-        fn from(value: &[Card; 5]) -> Self {
-            let mut tree_map = BTreeMap::<Card, u8>::new();
-            for &card in value {
-                *tree_map.entry(card).or_default() += 1;
-            }
-            let mut hand_type = HandType::HighCard;
-            for count in tree_map.values() {
-                hand_type = match (count, hand_type) {
-                    (2, HandType::HighCard) => HandType::OnePair,
-                    (2, HandType::OnePair) => HandType::TwoPair,
-                    (2, HandType::ThreeOfAKind) | (3, HandType::OnePair) => HandType::FullHouse,
-                    (3, HandType::HighCard) => HandType::ThreeOfAKind,
-                    (4, _) => HandType::FourOfAKind,
-                    (5, _) => HandType::FiveOfAKind,
-                    _ => hand_type,
-                }
-            }
-            hand_type
+    // Part 2, `J` is a joker, the weakest card but wild.
+    #[must_use]
+    pub fn jokers() -> Self {
+        let mut order = Card::ALL;
+        order[..=9].rotate_right(1);
+        Rules {
+            order,
+            wild: vec![Card::Jack],
+            tie_break: TieBreak::Dealt,
         }
     }
 
-    #[derive(Debug, Copy, Clone, Eq, PartialEq)]
-    struct Hand {
-        hand_type: HandType,
-        cards: [Card; 5],
-        bid: u64,
+    /// The position of `card` in `order`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `order` does not contain `card`.
+    #[must_use]
+    pub fn strength(&self, card: Card) -> u8 {
+        let position = self.order.iter().position(|&c| c == card);
+        u8::try_from(position.expect("every card is in the order")).unwrap()
+    }
+
+    #[must_use]
+    pub fn is_wild(&self, card: Card) -> bool {
+        self.wild.contains(&card)
     }
 
-    impl Ord for Hand {
-        fn cmp(&self, other: &Self) -> Ordering {
-            self.hand_type
-                .cmp(&other.hand_type)
-                .then(self.cards.cmp(&other.cards))
+    // The card strengths hands of the same type are compared by.
+    fn tie_break_key(&self, mut cards: [Card; 5]) -> [u8; 5] {
+        if self.tie_break == TieBreak::Grouped {
+            let count = |card: Card| cards.iter().filter(|&&c| c == card).count();
+            let mut grouped = cards;
+            grouped.sort_by(|&a, &b| {
+                count(b)
+                    .cmp(&count(a))
+                    .then(self.strength(b).cmp(&self.strength(a)))
+            });
+            cards = grouped;
         }
+        cards.map(|card| self.strength(card))
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Hand {
+    pub r#type: HandType,
+    pub cards: [Card; 5],
+    pub bid: u64,
+    // card strengths under the rules the hand was dealt with
+    tie_break: [u8; 5],
+    // the cards with every wild card replaced under those rules
+    substituted: [Card; 5],
+}
+
+impl Ord for Hand {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.r#type
+            .cmp(&other.r#type)
+            .then(self.tie_break.cmp(&other.tie_break))
     }
+}
 
-    impl PartialOrd for Hand {
-        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-            Some(self.cmp(other))
+impl PartialOrd for Hand {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Hand {
+    #[must_use]
+    pub fn new(cards: [Card; 5], bid: u64, rules: &Rules) -> Hand {
+        Hand {
+            r#type: HandType::classify(&cards, rules),
+            cards,
+            bid,
+            tie_break: rules.tie_break_key(cards),
+            substituted: substitute_wilds(cards, rules),
         }
     }
 
-    impl Hand {
-        fn from_line(line: &str) -> Hand {
-            let mut cards = [Card::Two; 5];
-            let (card_chars, bid) = line.split_once(' ').unwrap();
-            for (i, c) in card_chars.chars().enumerate() {
-                cards[i] = c.into();
-            }
-            Hand {
-                hand_type: HandType::from(&cards),
-                cards,
-                bid: bid.parse().unwrap(),
-            }
+    /// Parses a `cards bid` line.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the line is not five cards followed by a bid.
+    #[must_use]
+    pub fn from_line(line: &str, rules: &Rules) -> Hand {
+        let mut cards = [Card::Two; 5];
+        let (card_chars, bid) = line.split_once(' ').unwrap();
+        for (i, c) in card_chars.chars().enumerate() {
+            cards[i] = c.into();
         }
+        Hand::new(cards, bid.parse().unwrap(), rules)
     }
 }
 
-mod part_2 {
-    use std::cmp::Ordering;
-    use std::collections::BTreeMap;
+// How the wild cards of a hand reach its type.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Substitution {
+    // the hand with every wild card replaced
+    pub cards: [Card; 5],
+    // the position of every wild card and the card it becomes
+    pub replaced: Vec<(usize, Card)>,
+    // the type if the wild cards only counted as themselves
+    pub natural_type: HandType,
+}
 
-    fn solution(input: &str) -> u64 {
-        let mut hands = parse_input(input);
-        hands.sort();
-        hands
-            .iter()
-            .enumerate()
-            .map(|(rank, hand)| {
-                // eprintln!("{hand:?}");
-                hand.bid * (rank as u64 + 1)
+impl Hand {
+    // What the wild cards of the hand stand in for under the rules it was dealt with.
+    #[must_use]
+    pub fn substitution(&self) -> Substitution {
+        // a wild card never stands in for itself, so the changed cards are the wild ones
+        let replaced = (0..5)
+            .filter(|&i| self.substituted[i] != self.cards[i])
+            .map(|i| (i, self.substituted[i]))
+            .collect();
+        Substitution {
+            cards: self.substituted,
+            replaced,
+            // the standard rules have no wild cards
+            natural_type: HandType::classify(&self.cards, &Rules::standard()),
+        }
+    }
+}
+
+// Every wild card becomes a card of the biggest group of the other cards, the strongest one if
+// several groups are just as big. Five wild cards become five of the strongest card.
+fn substitute_wilds(mut cards: [Card; 5], rules: &Rules) -> [Card; 5] {
+    let count = |card: Card| cards.iter().filter(|&&c| c == card).count();
+    let strongest = rules.order.iter().rev().find(|&&card| !rules.is_wild(card));
+    let target = cards
+        .iter()
+        .copied()
+        .filter(|&card| !rules.is_wild(card))
+        .max_by_key(|&card| (count(card), rules.strength(card)))
+        .or(strongest.copied())
+        .unwrap_or(Card::Ace);
+    for card in &mut cards {
+        if rules.is_wild(*card) {
+            *card = target;
+        }
+    }
+    cards
+}
+
+fn cards_text(cards: [Card; 5]) -> String {
+    cards.iter().map(ToString::to_string).collect()
+}
+
+/// A table of every hand from weakest to strongest, showing what its wild cards stand in for.
+///
+/// # Panics
+///
+/// Panics if a line is not a valid hand.
+#[must_use]
+pub fn ranked_listing(input: &str, rules: &Rules) -> String {
+    Leaderboard::new(input, rules).standings_text(true)
+}
+
+/// Every hand ranked from weakest to strongest.
+///
+/// # Panics
+///
+/// Panics if a line is not a valid hand.
+#[must_use]
+pub fn ranked_hands(input: &str, rules: &Rules) -> Vec<Hand> {
+    let mut hands: Vec<Hand> = input
+        .lines()
+        .map(|line| Hand::from_line(line, rules))
+        .collect();
+    hands.sort();
+    hands
+}
+
+/// The sum of every bid multiplied by the rank of its hand.
+///
+/// # Panics
+///
+/// Panics if a line is not a valid hand.
+#[must_use]
+pub fn total_winnings(input: &str, rules: &Rules) -> u64 {
+    Leaderboard::new(input, rules).total_winnings()
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Standing {
+    // 1 for the weakest hand
+    pub rank: u64,
+    pub hand: Hand,
+    // the bid multiplied by the rank
+    pub winnings: u64,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct TypeSummary {
+    pub r#type: HandType,
+    pub hands: usize,
+    pub winnings: u64,
+}
+
+// The ranked hands of a game, weakest first.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Leaderboard {
+    pub standings: Vec<Standing>,
+}
+
+impl Leaderboard {
+    /// Ranks every hand in `input` by `rules`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a line is not a valid hand.
+    #[must_use]
+    pub fn new(input: &str, rules: &Rules) -> Self {
+        let standings = ranked_hands(input, rules)
+            .into_iter()
+            .zip(1..)
+            .map(|(hand, rank)| Standing {
+                rank,
+                hand,
+                winnings: hand.bid * rank,
             })
+            .collect();
+        Leaderboard { standings }
+    }
+
+    // Only the hands of one of `types`, they keep their rank among all hands.
+    #[must_use]
+    pub fn filter(&self, types: &[HandType]) -> Leaderboard {
+        let standings = self
+            .standings
+            .iter()
+            .filter(|standing| types.contains(&standing.hand.r#type))
+            .copied()
+            .collect();
+        Leaderboard { standings }
+    }
+
+    #[must_use]
+    pub fn total_winnings(&self) -> u64 {
+        self.standings
+            .iter()
+            .map(|standing| standing.winnings)
             .sum()
     }
 
-    fn parse_input(input: &str) -> Vec<Hand> {
-        input.lines().map(Hand::from_line).collect()
+    // The hands and winnings of every type on the leaderboard, weakest type first.
+    #[must_use]
+    pub fn summary(&self) -> Vec<TypeSummary> {
+        let mut types = BTreeMap::<HandType, TypeSummary>::new();
+        for standing in &self.standings {
+            let r#type = standing.hand.r#type;
+            let summary = types.entry(r#type).or_insert(TypeSummary {
+                r#type,
+                hands: 0,
+                winnings: 0,
+            });
+            summary.hands += 1;
+            summary.winnings += standing.winnings;
+        }
+        types.into_values().collect()
+    }
+
+    // One line per hand, with `substitutions` also what its wild cards stand in for and its
+    // type if they only counted as themselves.
+    #[must_use]
+    pub fn standings_text(&self, substitutions: bool) -> String {
+        let mut text = format!(
+            "{:>4}  {:<5}  {:<15}  {:>4}  {:>8}",
+            "rank", "hand", "type", "bid", "winnings"
+        );
+        if substitutions {
+            write!(text, "  {:<5}  without wild cards", "as").unwrap();
+        }
+        text.push('\n');
+        for standing in &self.standings {
+            write!(
+                text,
+                "{:>4}  {}  {:<15}  {:>4}  {:>8}",
+                standing.rank,
+                cards_text(standing.hand.cards),
+                standing.hand.r#type,
+                standing.hand.bid,
+                standing.winnings,
+            )
+            .unwrap();
+            if substitutions {
+                let substitution = standing.hand.substitution();
+                if substitution.replaced.is_empty() {
+                    write!(text, "  {:<5}  -", "-").unwrap();
+                } else {
+                    write!(
+                        text,
+                        "  {}  {}",
+                        cards_text(substitution.cards),
+                        substitution.natural_type
+                    )
+                    .unwrap();
+                }
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    #[must_use]
+    pub fn to_text(&self) -> String {
+        let mut text = self.standings_text(false);
+        writeln!(text, "\n{:<15}  {:>5}  {:>8}", "type", "hands", "winnings").unwrap();
+        for summary in self.summary() {
+            writeln!(
+                text,
+                "{:<15}  {:>5}  {:>8}",
+                summary.r#type, summary.hands, summary.winnings
+            )
+            .unwrap();
+        }
+        writeln!(
+            text,
+            "{:<15}  {:>5}  {:>8}",
+            "total",
+            self.standings.len(),
+            self.total_winnings()
+        )
+        .unwrap();
+        text
+    }
+
+    #[must_use]
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("rank,hand,type,bid,winnings\n");
+        for standing in &self.standings {
+            writeln!(
+                csv,
+                "{},{},{},{},{}",
+                standing.rank,
+                cards_text(standing.hand.cards),
+                standing.hand.r#type,
+                standing.hand.bid,
+                standing.winnings,
+            )
+            .unwrap();
+        }
+        csv
+    }
+
+    #[must_use]
+    pub fn summary_csv(&self) -> String {
+        let mut csv = String::from("type,hands,winnings\n");
+        for summary in self.summary() {
+            writeln!(
+                csv,
+                "{},{},{}",
+                summary.r#type, summary.hands, summary.winnings
+            )
+            .unwrap();
+        }
+        csv
+    }
+}
+
+mod part_1 {
+    use crate::Rules;
+
+    fn solution(input: &str) -> u64 {
+        super::total_winnings(input, &Rules::standard())
+    }
+
+    #[test]
+    fn test_parse_example_input() {
+        let input = super::EXAMPLE_INPUT;
+        assert_eq!(solution(input), 6440);
+    }
+
+    #[test]
+    fn test_parse_input() {
+        eprintln!("Solution Day 7 Part 1: [{}]", solution(super::INPUT));
+    }
+}
+
+mod part_2 {
+    use crate::Rules;
+
+    fn solution(input: &str) -> u64 {
+        super::total_winnings(input, &Rules::jokers())
     }
 
     #[test]
@@ -173,123 +607,311 @@ mod part_2 {
     fn test_parse_input() {
         eprintln!("Solution Day 7 Part 2: [{}]", solution(super::INPUT));
     }
+}
 
-    #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
-    enum Card {
-        Joker,
-        Two,
-        Three,
-        Four,
-        Five,
-        Six,
-        Seven,
-        Eight,
-        Nine,
-        Ten,
-        Queen,
-        King,
-        Ace,
-    }
-
-    impl From<char> for Card {
-        fn from(c: char) -> Self {
-            match c {
-                '2' => Card::Two,
-                '3' => Card::Three,
-                '4' => Card::Four,
-                '5' => Card::Five,
-                '6' => Card::Six,
-                '7' => Card::Seven,
-                '8' => Card::Eight,
-                '9' => Card::Nine,
-                'T' => Card::Ten,
-                'J' => Card::Joker,
-                'Q' => Card::Queen,
-                'K' => Card::King,
-                'A' => Card::Ace,
-                _ => panic!("invalid card: {c}"),
-            }
+mod variants {
+    use crate::{Card, Hand, Rules};
+
+    fn cards(text: &str) -> [Card; 5] {
+        let mut cards = [Card::Two; 5];
+        for (i, c) in text.chars().enumerate() {
+            cards[i] = c.into();
         }
+        cards
     }
 
-    #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
-    enum HandType {
-        HighCard,
-        OnePair,
-        TwoPair,
-        ThreeOfAKind,
-        FullHouse,
-        FourOfAKind,
-        FiveOfAKind,
+    fn hand(text: &str, rules: &Rules) -> Hand {
+        Hand::new(cards(text), 1, rules)
     }
 
-    impl From<&[Card; 5]> for HandType {
-        fn from(value: &[Card; 5]) -> Self {
-            let mut tree_map = BTreeMap::<Card, u8>::new();
-            for &card in value {
-                *tree_map.entry(card).or_default() += 1;
-            }
-            let mut hand_type = HandType::HighCard;
-            let mut jokers = *tree_map.get(&Card::Joker).unwrap_or(&0);
-            let mut card_sets: Vec<_> = tree_map.into_iter().collect();
-            card_sets.sort_by_key(|&(_, count)| count);
-            card_sets.reverse();
-
-            for (card, count) in card_sets {
-                if card == Card::Joker {
-                    continue;
-                };
-                hand_type = match (count + jokers, hand_type) {
-                    (2, HandType::HighCard) => HandType::OnePair,
-                    (2, HandType::OnePair) => HandType::TwoPair,
-                    (2, HandType::ThreeOfAKind) | (3, HandType::OnePair) => HandType::FullHouse,
-                    (3, HandType::HighCard) => HandType::ThreeOfAKind,
-                    (4, _) => HandType::FourOfAKind,
-                    (5, _) => HandType::FiveOfAKind,
-                    _ => hand_type,
-                };
-                jokers = 0;
-            }
-            if jokers == 5 {
-                hand_type = HandType::FiveOfAKind;
-            }
-            hand_type
+    #[test]
+    fn test_jokers_order() {
+        let rules = Rules::jokers();
+        assert_eq!(rules.strength(Card::Jack), 0);
+        assert_eq!(rules.strength(Card::Two), 1);
+        assert_eq!(rules.strength(Card::Ten), 9);
+        assert_eq!(rules.strength(Card::Queen), 10);
+        assert_eq!(rules.strength(Card::Ace), 12);
+    }
+
+    #[test]
+    fn test_classify_by_rules() {
+        use crate::HandType;
+
+        for (text, standard, jokers) in [
+            ("32T3K", HandType::OnePair, HandType::OnePair),
+            ("KTJJT", HandType::TwoPair, HandType::FourOfAKind),
+            ("QQQJA", HandType::ThreeOfAKind, HandType::FourOfAKind),
+            ("JJJJJ", HandType::FiveOfAKind, HandType::FiveOfAKind),
+            ("2345J", HandType::HighCard, HandType::OnePair),
+        ] {
+            assert_eq!(hand(text, &Rules::standard()).r#type, standard);
+            assert_eq!(hand(text, &Rules::jokers()).r#type, jokers);
         }
     }
 
-    #[derive(Debug, Copy, Clone, Eq, PartialEq)]
-    struct Hand {
-        hand_type: HandType,
-        cards: [Card; 5],
-        bid: u64,
+    #[test]
+    fn test_deuces_wild() {
+        let rules = Rules {
+            wild: vec![Card::Two],
+            ..Rules::standard()
+        };
+        assert_eq!(hand("22AKQ", &rules).r#type, crate::HandType::ThreeOfAKind);
+        // a deuce still ranks lowest when breaking ties
+        assert!(hand("A2AKQ", &rules) > hand("2AAKQ", &rules));
+        // ranked high card, pair, three of a kind
+        assert_eq!(
+            super::total_winnings("22345 1\n6789T 2\nJJQKA 3", &rules),
+            2 + 3 * 2 + 3
+        );
     }
 
-    impl Ord for Hand {
-        fn cmp(&self, other: &Self) -> Ordering {
-            self.hand_type
-                .cmp(&other.hand_type)
-                .then(self.cards.cmp(&other.cards))
+    #[test]
+    fn test_grouped_tie_break() {
+        let rules = Rules {
+            tie_break: crate::TieBreak::Grouped,
+            ..Rules::standard()
+        };
+        // dealt order favours the ace, grouped order the pair of kings
+        assert!(hand("2KK33", &rules) > hand("A2233", &rules));
+        let standard = Rules::standard();
+        assert!(hand("A2233", &standard) > hand("2KK33", &standard));
+    }
+}
+
+// Checks both classifiers against trying every card for every wild card, over all 13^5 hands.
+mod verification {
+    use crate::{Card, HandType, Rules};
+
+    const HANDS: usize = 13 * 13 * 13 * 13 * 13;
+
+    fn nth_hand(mut index: usize) -> [Card; 5] {
+        let mut cards = [Card::Two; 5];
+        for card in &mut cards {
+            *card = Card::ALL[index % 13];
+            index /= 13;
         }
+        cards
+    }
+
+    // The best type over every way to replace the wild cards. The order of the replacements
+    // does not change the type, so only non-decreasing choices are tried.
+    fn brute_force(cards: [Card; 5], rules: &Rules) -> HandType {
+        let wild = cards.iter().position(|&card| rules.is_wild(card));
+        let Some(wild) = wild else {
+            return HandType::from_signature(&cards, &Rules::standard());
+        };
+        best_substitution(cards, wild, 0, rules)
+    }
+
+    fn best_substitution(cards: [Card; 5], index: usize, first: usize, rules: &Rules) -> HandType {
+        let next = (index + 1..5).find(|&i| rules.is_wild(cards[i]));
+        Card::ALL[first..]
+            .iter()
+            .enumerate()
+            .map(|(offset, &substitute)| {
+                let mut cards = cards;
+                cards[index] = substitute;
+                match next {
+                    Some(next) => best_substitution(cards, next, first + offset, rules),
+                    None => HandType::from_signature(&cards, &Rules::standard()),
+                }
+            })
+            .max()
+            .unwrap()
     }
 
-    impl PartialOrd for Hand {
-        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-            Some(self.cmp(other))
+    fn verify(rules: &Rules) {
+        let mut counts = [0usize; 7];
+        for index in 0..HANDS {
+            let cards = nth_hand(index);
+            let expected = brute_force(cards, rules);
+            assert_eq!(HandType::classify(&cards, rules), expected, "{cards:?}");
+            assert_eq!(
+                HandType::from_signature(&cards, rules),
+                expected,
+                "{cards:?}"
+            );
+            counts[expected as usize] += 1;
         }
+        assert_eq!(counts.iter().sum::<usize>(), HANDS);
     }
 
-    impl Hand {
-        fn from_line(line: &str) -> Hand {
-            let mut cards = [Card::Two; 5];
-            let (card_chars, bid) = line.split_once(' ').unwrap();
-            for (i, c) in card_chars.chars().enumerate() {
-                cards[i] = c.into();
-            }
-            Hand {
-                hand_type: HandType::from(&cards),
-                cards,
-                bid: bid.parse().unwrap(),
-            }
+    #[test]
+    fn test_all_hands_standard() {
+        verify(&Rules::standard());
+    }
+
+    #[test]
+    fn test_all_hands_jokers() {
+        verify(&Rules::jokers());
+    }
+
+    #[test]
+    fn test_substitution_reaches_type() {
+        let rules = Rules::jokers();
+        for index in 0..HANDS {
+            let hand = crate::Hand::new(nth_hand(index), 0, &rules);
+            let substitution = hand.substitution();
+            let reached = HandType::from_signature(&substitution.cards, &Rules::standard());
+            assert_eq!(reached, hand.r#type, "{hand:?}");
+            assert!(substitution.natural_type <= hand.r#type);
+        }
+    }
+
+    #[test]
+    fn test_type_counts() {
+        // the number of ordered hands of every type without wild cards
+        let mut counts = [0usize; 7];
+        for index in 0..HANDS {
+            counts[HandType::from_signature(&nth_hand(index), &Rules::standard()) as usize] += 1;
+        }
+        assert_eq!(counts, [154_440, 171_600, 25_740, 17_160, 1_560, 780, 13]);
+    }
+}
+
+mod explain {
+    use crate::{Hand, Rules};
+
+    fn joker_hand(text: &str) -> Hand {
+        Hand::from_line(&format!("{text} 1"), &Rules::jokers())
+    }
+
+    #[test]
+    fn test_substitution() {
+        use crate::HandType;
+
+        let substitution = joker_hand("T55J5").substitution();
+        assert_eq!(substitution.replaced, vec![(3, crate::Card::Five)]);
+        assert_eq!(substitution.natural_type, HandType::ThreeOfAKind);
+
+        let substitution = joker_hand("KTJJT").substitution();
+        assert_eq!(super::cards_text(substitution.cards), "KTTTT");
+        assert_eq!(substitution.natural_type, HandType::TwoPair);
+
+        // with groups of the same size the stronger card wins
+        let substitution = joker_hand("2233J").substitution();
+        assert_eq!(super::cards_text(substitution.cards), "22333");
+
+        let substitution = joker_hand("JJJJJ").substitution();
+        assert_eq!(super::cards_text(substitution.cards), "AAAAA");
+        assert_eq!(substitution.natural_type, HandType::FiveOfAKind);
+
+        let substitution = joker_hand("32T3K").substitution();
+        assert!(substitution.replaced.is_empty());
+    }
+
+    #[test]
+    fn test_substitution_follows_the_rules_of_the_hand() {
+        let deuces = Rules {
+            wild: vec![crate::Card::Two],
+            ..Rules::standard()
+        };
+        let hand = Hand::from_line("22AKJ 1", &deuces);
+        assert_eq!(super::cards_text(hand.substitution().cards), "AAAKJ");
+        let hand = Hand::from_line("22AKJ 1", &Rules::jokers());
+        assert_eq!(super::cards_text(hand.substitution().cards), "22AK2");
+    }
+
+    #[test]
+    fn test_ranked_listing() {
+        assert_eq!(
+            super::ranked_listing(super::EXAMPLE_INPUT, &Rules::jokers()),
+            "\
+rank  hand   type              bid  winnings  as     without wild cards
+   1  32T3K  one pair          765       765  -      -
+   2  KK677  two pair           28        56  -      -
+   3  T55J5  four of a kind    684      2052  T5555  three of a kind
+   4  QQQJA  four of a kind    483      1932  QQQQA  three of a kind
+   5  KTJJT  four of a kind    220      1100  KTTTT  two pair
+"
+        );
+    }
+}
+
+mod leaderboard {
+    use crate::{Leaderboard, Rules};
+
+    fn example(rules: &Rules) -> Leaderboard {
+        Leaderboard::new(super::EXAMPLE_INPUT, rules)
+    }
+
+    #[test]
+    fn test_standard_text() {
+        assert_eq!(
+            example(&Rules::standard()).to_text(),
+            "\
+rank  hand   type              bid  winnings
+   1  32T3K  one pair          765       765
+   2  KTJJT  two pair          220       440
+   3  KK677  two pair           28        84
+   4  T55J5  three of a kind   684      2736
+   5  QQQJA  three of a kind   483      2415
+
+type             hands  winnings
+one pair             1       765
+two pair             2       524
+three of a kind      2      5151
+total                5      6440
+"
+        );
+    }
+
+    #[test]
+    fn test_jokers_csv() {
+        let leaderboard = example(&Rules::jokers());
+        assert_eq!(
+            leaderboard.to_csv(),
+            "\
+rank,hand,type,bid,winnings
+1,32T3K,one pair,765,765
+2,KK677,two pair,28,56
+3,T55J5,four of a kind,684,2052
+4,QQQJA,four of a kind,483,1932
+5,KTJJT,four of a kind,220,1100
+"
+        );
+        assert_eq!(
+            leaderboard.summary_csv(),
+            "\
+type,hands,winnings
+one pair,1,765
+two pair,1,56
+four of a kind,3,5084
+"
+        );
+        assert_eq!(leaderboard.total_winnings(), 5905);
+    }
+
+    #[test]
+    fn test_filter_by_type() {
+        use crate::HandType;
+
+        let leaderboard = example(&Rules::jokers());
+        let fours = leaderboard.filter(&[HandType::FourOfAKind]);
+        let ranks: Vec<u64> = fours.standings.iter().map(|s| s.rank).collect();
+        assert_eq!(ranks, vec![3, 4, 5]);
+        assert_eq!(fours.total_winnings(), 5084);
+        assert_eq!(fours.summary().len(), 1);
+        assert!(fours
+            .standings_text(true)
+            .ends_with("   5  KTJJT  four of a kind    220      1100  KTTTT  two pair\n"));
+        let none = leaderboard.filter(&[HandType::FiveOfAKind]);
+        assert!(none.standings.is_empty());
+        assert!(none
+            .to_text()
+            .ends_with("total                0         0\n"));
+    }
+
+    #[test]
+    fn test_summary_adds_up() {
+        for rules in [Rules::standard(), Rules::jokers()] {
+            let leaderboard = Leaderboard::new(super::INPUT, &rules);
+            let summary = leaderboard.summary();
+            let hands: usize = summary.iter().map(|s| s.hands).sum();
+            let winnings: u64 = summary.iter().map(|s| s.winnings).sum();
+            assert_eq!(hands, leaderboard.standings.len());
+            assert_eq!(winnings, leaderboard.total_winnings());
         }
     }
 }
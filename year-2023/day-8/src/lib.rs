@@ -109,7 +109,7 @@ mod part_2 {
 
         loop {
             // Invariant: n odd
-            debug_assert!(n % 2 == 1, "n = {} is even", n);
+            debug_assert!(n % 2 == 1, "n = {n} is even");
 
             if n > m {
                 swap(&mut n, &mut m);